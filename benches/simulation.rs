@@ -0,0 +1,20 @@
+use advent_of_code_2022_11::{monkey_business, MonkeyTroop};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+const SAMPLE: &str = include_str!("../example-input.txt");
+
+fn simulation(c: &mut Criterion) {
+    c.bench_function("monkey_business 10000 rounds", |b| {
+        b.iter(|| monkey_business(black_box(SAMPLE), 10000))
+    });
+}
+
+fn parse(c: &mut Criterion) {
+    c.bench_function("parse", |b| {
+        b.iter(|| MonkeyTroop::parse(black_box(SAMPLE)))
+    });
+}
+
+criterion_group!(benches, simulation, parse);
+criterion_main!(benches);
@@ -1,200 +1,4479 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::num::ParseIntError;
+use core::str::FromStr;
+use num::{Integer, PrimInt};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use sscanf::sscanf;
-use sscanf::RegexRepresentation;
-use std::num::ParseIntError;
-use std::str::FromStr;
+#[cfg(feature = "std")]
+use std::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::hash::Hash;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+use thiserror::Error;
+
+/// The default type of an item's worry level
+pub type Item = u64;
+
+/// An integer type that worry levels can be stored in, such as `u32`, `u64` or `u128`. A signed
+/// type such as `i64` lets subtraction go below zero, with negative worry levels tested for
+/// divisibility like any other and otherwise reduced to their Euclidean remainder
+/// # Examples
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let input = "Monkey 0:
+///   Starting items: 2, 3
+///   Operation: new = old - 5
+///   Test: divisible by 3
+///     If true: throw to monkey 1
+///     If false: throw to monkey 2
+///
+/// Monkey 1:
+///   Starting items:
+///   Operation: new = old * 2
+///   Test: divisible by 5
+///     If true: throw to monkey 0
+///     If false: throw to monkey 0
+///
+/// Monkey 2:
+///   Starting items:
+///   Operation: new = old + 1
+///   Test: divisible by 7
+///     If true: throw to monkey 0
+///     If false: throw to monkey 0";
+///
+/// let mut troop: MonkeyTroop<i64> = input.parse().unwrap();
+/// let thrown: Vec<_> = troop
+///     .throws(1)
+///     .map(|throw| throw.map(|(_, thrown)| (thrown.item, thrown.to_monkey)))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// // -3 is divisible by 3 but -2 isn't, and both are brought back into range by the next monkey
+/// assert_eq!(vec![(-3, 1), (-2, 2), (99, 0), (104, 0)], thrown);
+/// # }
+/// ```
+pub trait Worry:
+    PrimInt + Integer + FromStr<Err = ParseIntError> + fmt::Display + fmt::Debug + Send
+{
+}
 
-type Item = usize;
+impl<T> Worry for T where
+    T: PrimInt + Integer + FromStr<Err = ParseIntError> + fmt::Display + fmt::Debug + Send
+{
+}
 
-enum Op {
+/// An arithmetic operator in a monkey's operation
+///
+/// The left operand is always the one written first, which matters for the operators that
+/// aren't commutative
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let new = |operation: &str, old: u64, checked: bool| {
+///     let input = format!(
+///         "Monkey 0:
+///   Starting items: {old}
+///   Operation: new = {operation}
+///   Test: divisible by 2
+///     If true: throw to monkey 1
+///     If false: throw to monkey 1
+///
+/// Monkey 1:
+///   Starting items:
+///   Operation: new = old
+///   Test: divisible by 3
+///     If true: throw to monkey 0
+///     If false: throw to monkey 0"
+///     );
+///     let mut troop = MonkeyTroop::parse(&input).unwrap().without_modulus();
+///     if checked {
+///         troop = troop.with_checked_arithmetic();
+///     }
+///     troop.throws(1).next().unwrap().unwrap().1.became
+/// };
+///
+/// for (operation, expected) in [
+///     ("old - old", 0),
+///     ("old - 5", 7),
+///     ("20 - old", 8),
+///     ("5 - old", 0),
+///     ("20 - 5", 15),
+///     ("old / old", 1),
+///     ("old / 4", 3),
+///     ("48 / old", 4),
+///     ("48 / 4", 12),
+///     ("old ^ old", 8916100448256),
+///     ("old ^ 2", 144),
+///     ("2 ^ old", 4096),
+///     ("2 ^ 10", 1024),
+/// ] {
+///     assert_eq!(expected, new(operation, 12, false), "{operation}");
+///     assert_eq!(expected, new(operation, 12, true), "checked {operation}");
+/// }
+/// # }
+/// ```
+///
+/// Operations can also subtract, saturating at zero, and divide
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::{ArithmeticError, MonkeyTroop};
+///
+/// let input = concat!(
+///     "Monkey 0:\n",
+///     "  Starting items: 100, 5\n",
+///     "  Operation: new = old - 10\n",
+///     "  Test: divisible by 97\n",
+///     "    If true: throw to monkey 1\n",
+///     "    If false: throw to monkey 1\n",
+///     "\n",
+///     "Monkey 1:\n",
+///     "  Starting items: 40\n",
+///     "  Operation: new = old / 2\n",
+///     "  Test: divisible by 89\n",
+///     "    If true: throw to monkey 0\n",
+///     "    If false: throw to monkey 0"
+/// );
+///
+/// let mut troop = MonkeyTroop::parse(input).unwrap().with_worry_divisor(3);
+/// troop.step_round().unwrap();
+/// assert_eq!(vec![vec![6, 5, 0], vec![]], troop.held_items());
+///
+/// let mut troop = MonkeyTroop::parse(&input.replace("old / 2", "old / 0")).unwrap();
+/// assert!(matches!(
+///     troop.step_round(),
+///     Err(ArithmeticError::DivideByZero)
+/// ));
+/// # }
+/// ```
+///
+/// Dividing a remainder doesn't give the remainder of the quotient, and nor does saturating
+/// subtraction or raising something to the power of a remainder, so a troop with `/` applied to
+/// `old`, `-` applied to an unsigned `old`, or `old` as an exponent, keeps raw worry levels as it
+/// does with a worry divisor. Operations then fail with `ArithmeticError::Overflow` if they don't
+/// fit in the worry type
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let input = "Monkey 0:
+///   Starting items: 31
+///   Operation: new = old - 10
+///   Test: divisible by 5
+///     If true: throw to monkey 1
+///     If false: throw to monkey 2
+///
+/// Monkey 1:
+///   Starting items:
+///   Operation: new = old
+///   Test: divisible by 3
+///     If true: throw to monkey 0
+///     If false: throw to monkey 2
+///
+/// Monkey 2:
+///   Starting items:
+///   Operation: new = old
+///   Test: divisible by 1
+///     If true: throw to monkey 0
+///     If false: throw to monkey 1";
+///
+/// // 21 - 10 isn't divisible by 5, though (21 mod 15) - 10 saturates to 0, which is
+/// let mut troop = MonkeyTroop::parse(input).unwrap();
+/// troop.run_checked_modulo(2).unwrap();
+/// assert_eq!(vec![vec![11], vec![], vec![]], troop.held_items());
+///
+/// let input = input.replace("31", "5").replace("old - 10", "2 ^ old");
+/// let mut troop = MonkeyTroop::parse(&input).unwrap();
+/// troop.run_checked_modulo(2).unwrap();
+/// assert_eq!(vec![vec![1 << 32], vec![], vec![]], troop.held_items());
+/// assert!(troop.step_round().is_err());
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Op {
+    /// `*`
     Times,
+    /// `+`
     Plus,
+    /// `-`, saturating at zero for unsigned worry types
+    Minus,
+    /// `/`
+    Divide,
+    /// `^`, raising the left operand to the power of the right
+    Power,
 }
 
 impl Op {
-    fn on(&self, item1: Item, item2: Item) -> Item {
-        match self {
-            Op::Times => item1 * item2,
-            Op::Plus => item1 + item2,
+    /// Applies the operation modulo `modulo` in a way that can't overflow. Subtraction saturates
+    /// at zero and division by zero is an error
+    fn on<T: Worry>(&self, item1: T, item2: T, modulo: T) -> Result<T, ArithmeticError> {
+        Ok(match self {
+            Op::Times => mul_mod(item1, item2, modulo),
+            Op::Plus => add_mod(item1, item2, modulo),
+            Op::Minus => item1.saturating_sub(item2) % modulo,
+            Op::Divide => {
+                item1
+                    .checked_div(&item2)
+                    .ok_or(ArithmeticError::DivideByZero)?
+                    % modulo
+            }
+            Op::Power if item2 < T::zero() => return Err(ArithmeticError::NegativeExponent),
+            Op::Power => pow_mod(item1, item2, modulo),
+        })
+    }
+
+    /// Applies the operation modulo `modulo`, failing if an intermediate result doesn't fit in
+    /// the worry type
+    fn checked_on<T: Worry>(&self, item1: T, item2: T, modulo: T) -> Result<T, ArithmeticError> {
+        let result = match self {
+            Op::Times => (item1 % modulo).checked_mul(&(item2 % modulo)),
+            Op::Plus => (item1 % modulo).checked_add(&(item2 % modulo)),
+            Op::Power if item2 < T::zero() => return Err(ArithmeticError::NegativeExponent),
+            Op::Power => item2
+                .to_usize()
+                .and_then(|exp| num::traits::checked_pow(item1 % modulo, exp)),
+            Op::Minus | Op::Divide => return self.on(item1, item2, modulo),
+        };
+        Ok(result.ok_or(ArithmeticError::Overflow)? % modulo)
+    }
+}
+
+/// Adds `a` and `b` modulo `modulo` without overflowing
+fn add_mod<T: Worry>(a: T, b: T, modulo: T) -> T {
+    let (a, b) = (a.mod_floor(&modulo), b.mod_floor(&modulo));
+    match a >= modulo - b {
+        true => a - (modulo - b),
+        false => a + b,
+    }
+}
+
+/// Multiplies `a` and `b` modulo `modulo`. When the product would overflow it's widened to a
+/// `u128` if everything fits in a `u64`, and otherwise found by repeated doubling
+fn mul_mod<T: Worry>(a: T, b: T, modulo: T) -> T {
+    let (mut a, mut b) = (a.mod_floor(&modulo), b.mod_floor(&modulo));
+    if let Some(product) = a.checked_mul(&b) {
+        return product % modulo;
+    }
+    if let (Some(a), Some(b), Some(m)) = (a.to_u64(), b.to_u64(), modulo.to_u64()) {
+        return T::from(mul_mod_u64(a, b, m)).expect("The product should be less than the modulus");
+    }
+
+    let mut result = T::zero();
+    while !b.is_zero() {
+        if b & T::one() == T::one() {
+            result = add_mod(result, a, modulo);
         }
+        a = add_mod(a, a, modulo);
+        b = b >> 1;
     }
+    result
 }
 
-impl RegexRepresentation for Op {
-    const REGEX: &'static str = r"[*+]";
+/// Multiplies `a` and `b` modulo `m` with a `u128` intermediate, which can't overflow
+fn mul_mod_u64(a: u64, b: u64, m: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(m)) as u64
+}
+
+/// Raises `base` to the power of `exp` modulo `modulo` by repeated squaring
+fn pow_mod<T: Worry>(base: T, mut exp: T, modulo: T) -> T {
+    let mut base = base.mod_floor(&modulo);
+    let mut result = T::one() % modulo;
+    while !exp.is_zero() {
+        if exp & T::one() == T::one() {
+            result = mul_mod(result, base, modulo);
+        }
+        base = mul_mod(base, base, modulo);
+        exp = exp >> 1;
+    }
+    result
 }
 
 impl FromStr for Op {
-    type Err = std::io::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "*" => Ok(Op::Times),
             "+" => Ok(Op::Plus),
-            x => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Invalid operation {x}"),
-            )),
+            "-" => Ok(Op::Minus),
+            "/" => Ok(Op::Divide),
+            "^" => Ok(Op::Power),
+            x => Err(ParseError::UnknownOperator(x.to_string())),
         }
     }
 }
 
-enum Expr {
-    Num(Item),
+/// Writes the operator as it appears in the puzzle input
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Op::Times => "*",
+            Op::Plus => "+",
+            Op::Minus => "-",
+            Op::Divide => "/",
+            Op::Power => "^",
+        })
+    }
+}
+
+/// An operand in a monkey's operation
+///
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let input = "Monkey 0:
+///   Starting items: 0
+///   Operation: new = old + round
+///   Test: divisible by 1000
+///     If true: throw to monkey 1
+///     If false: throw to monkey 1
+///
+/// Monkey 1:
+///   Starting items:
+///   Operation: new = old * 1
+///   Test: divisible by 7
+///     If true: throw to monkey 0
+///     If false: throw to monkey 0";
+///
+/// let mut troop = MonkeyTroop::parse(input).unwrap();
+/// let thrown: Vec<u64> = troop
+///     .throws(3)
+///     .filter_map(|throw| throw.ok())
+///     .filter(|&(monkey, _)| monkey == 0)
+///     .map(|(_, thrown)| thrown.item)
+///     .collect();
+/// assert_eq!(vec![1, 3, 6], thrown);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Expr<T = Item> {
+    /// A constant
+    Num(T),
+    /// The item's worry level before the operation
     Old,
+    /// The number of the round being played, counting from 1, written `round`
+    RoundVar,
 }
 
-impl Expr {
-    pub fn or(&self, old: Item) -> Item {
+impl<T: Worry> Expr<T> {
+    /// The value of the operand, given the item's worry level before the operation and the number
+    /// of the round being played
+    pub fn or(&self, old: T, round: T) -> T {
         match self {
             Expr::Num(item) => *item,
             Expr::Old => old,
+            Expr::RoundVar => round,
         }
     }
 }
 
-impl RegexRepresentation for Expr {
-    const REGEX: &'static str = r"old|\d+";
-}
-
-impl FromStr for Expr {
-    type Err = ParseIntError;
+impl<T: Worry> FromStr for Expr<T> {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "old" => Expr::Old,
-            x => Expr::Num(x.parse::<Item>()?),
+            "round" => Expr::RoundVar,
+            x => Expr::Num(x.parse::<T>()?),
         })
     }
 }
 
-struct ThrownItem {
-    item: Item,
-    to_monkey: usize,
+/// Writes the operand as it appears in the puzzle input
+impl<T: Worry> fmt::Display for Expr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Num(item) => write!(f, "{item}"),
+            Expr::Old => f.write_str("old"),
+            Expr::RoundVar => f.write_str("round"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Operation<T> {
+    lhs: Expr<T>,
+    op: Op,
+    rhs: Expr<T>,
+    /// The modulus the result is taken in, if the monkey has an `Operation mod:` line
+    modulo: Option<T>,
+}
+
+impl<T: Worry> Operation<T> {
+    /// Parses the operation `new = lhs op rhs`
+    #[cfg(feature = "std")]
+    fn parse(lhs: &str, op: &str, rhs: &str) -> Result<Self, ParseError> {
+        Ok(Operation {
+            lhs: lhs.parse()?,
+            op: op.parse()?,
+            rhs: rhs.parse()?,
+            modulo: None,
+        })
+    }
+
+    /// Applies the operation modulo `modulo`, which must be a multiple of the operation's own
+    /// modulus if it has one, so that the result can then be reduced to that modulus exactly
+    fn apply(&self, old: T, round: T, modulo: T, checked: bool) -> Result<T, ArithmeticError> {
+        let (lhs, rhs) = (self.lhs.or(old, round), self.rhs.or(old, round));
+        let new = match checked {
+            true => self.op.checked_on(lhs, rhs, modulo)?,
+            false => self.op.on(lhs, rhs, modulo)?,
+        };
+        Ok(self.modulo.map_or(new, |m| new % m))
+    }
+
+    /// Whether either operand is the number of the round
+    #[cfg(feature = "std")]
+    fn uses_round(&self) -> bool {
+        matches!(self.lhs, Expr::RoundVar) || matches!(self.rhs, Expr::RoundVar)
+    }
+
+    /// Whether the operation gives the same result modulo a multiple of the troop's modulus
+    /// whether it's given `old` or its remainder
+    fn is_modular(&self) -> bool {
+        let on_old = matches!(self.lhs, Expr::Old) || matches!(self.rhs, Expr::Old);
+        // Only unsigned subtraction saturates rather than going below zero
+        let signed = T::min_value() < T::zero();
+        match self.op {
+            Op::Times | Op::Plus => true,
+            Op::Minus => !on_old || signed,
+            Op::Divide => !on_old,
+            Op::Power => !matches!(self.rhs, Expr::Old),
+        }
+    }
+}
+
+/// Writes the operation as it appears after `Operation:` in the puzzle input
+impl<T: Worry> fmt::Display for Operation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "new = {} {} {}", self.lhs, self.op, self.rhs)
+    }
+}
+
+/// A monkey's operation specialised to its operands, with any constant already reduced modulo
+/// the troop's modulus. Only built when the square of a reduced worry level can't overflow, so
+/// it needs no overflow checks
+#[derive(Clone, Copy, Debug)]
+struct FastOp<T> {
+    modulo: T,
+    kind: FastOpKind<T>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum FastOpKind<T> {
+    /// `old * old`
+    Square,
+    /// `old * c` or `c * old`
+    Times(T),
+    /// `old + c` or `c + old`
+    Plus(T),
+}
+
+impl<T: Worry> FastOp<T> {
+    /// Specialises `operation` for `modulo`, or returns `None` if it has no fast path
+    fn new(operation: &Operation<T>, modulo: T) -> Option<Self> {
+        if modulo.is_zero() || modulo.checked_mul(&modulo).is_none() || operation.modulo.is_some() {
+            return None;
+        }
+
+        let kind = match (operation.lhs, operation.op, operation.rhs) {
+            (Expr::Old, Op::Times, Expr::Old) => FastOpKind::Square,
+            (Expr::Old, Op::Times, Expr::Num(c)) | (Expr::Num(c), Op::Times, Expr::Old) => {
+                FastOpKind::Times(c.mod_floor(&modulo))
+            }
+            (Expr::Old, Op::Plus, Expr::Num(c)) | (Expr::Num(c), Op::Plus, Expr::Old) => {
+                FastOpKind::Plus(c.mod_floor(&modulo))
+            }
+            _ => return None,
+        };
+
+        Some(FastOp { modulo, kind })
+    }
+
+    fn apply(&self, old: T) -> T {
+        let old = old.mod_floor(&self.modulo);
+        match self.kind {
+            FastOpKind::Square => old * old % self.modulo,
+            FastOpKind::Times(c) => old * c % self.modulo,
+            FastOpKind::Plus(c) => (old + c) % self.modulo,
+        }
+    }
+}
+
+/// An operation given as a closure by `Monkey::with_operation`
+#[derive(Clone)]
+struct CustomOp<T>(Arc<dyn Fn(T) -> T + Send + Sync>);
+
+impl<T> fmt::Debug for CustomOp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomOp")
+    }
+}
+
+/// An error encountered while parsing the puzzle input. Errors for a malformed line report the
+/// monkey and line, and chain the cause of the problem as their source where there is one
+/// # Examples
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+/// use std::error::Error;
+///
+/// let input = include_str!("../example-input.txt");
+/// let error = |from: &str, to: &str| MonkeyTroop::parse(&input.replace(from, to)).unwrap_err();
+///
+/// for (from, to, message) in [
+///     ("Monkey 1:", "Monkey one:", r#"Invalid header for monkey 1: "Monkey one:""#),
+///     ("79, 98", "79, x", r#"Invalid starting items for monkey 0: "  Starting items: 79, x""#),
+///     ("old * 19", "old % 19", r#"Invalid operation for monkey 0: "  Operation: new = old % 19""#),
+///     ("by 23", "by x", r#"Invalid or missing test for monkey 0: "  Test: divisible by x""#),
+///     ("true: throw to", "true: throw", r#"Invalid or missing target for monkey 0: "    If true: throw monkey 2""#),
+///     ("by 19", "by 0", "Monkey 1 tests for divisibility by zero"),
+///     ("monkey 3", "monkey 7", "Monkey 0 throws to monkey 7, which doesn't exist"),
+///     ("monkey 3", "monkey 0", "Monkey 0 throws to itself"),
+///     ("Monkey 2:", "Monkey 1:", "Monkey 1 is defined more than once"),
+///     ("Monkey 2:", "Monkey 4:", "Monkey 2 is missing"),
+///     (input, "", "Found 0 monkeys, but at least 2 are needed"),
+/// ] {
+///     assert_eq!(message, error(from, to).to_string());
+/// }
+///
+/// let unknown_operator = error("old * 19", "old % 19");
+/// let source = unknown_operator.source().unwrap();
+/// assert_eq!(r#"Unknown operator "%""#, source.to_string());
+/// assert!(source.source().is_none());
+///
+/// let bad_number = error("79, 98", "79, x");
+/// let source = bad_number.source().unwrap();
+/// assert_eq!("Invalid number", source.to_string());
+/// assert_eq!("invalid digit found in string", source.source().unwrap().to_string());
+///
+/// assert!(error("true: throw to", "true: throw").source().is_none());
+/// # }
+/// ```
+#[derive(Debug, Error)]
+pub enum ParseError {
+    /// The `Monkey N:` header line is missing or malformed
+    #[error("Invalid header for monkey {monkey}: {line:?}")]
+    BadHeader { monkey: usize, line: String },
+    /// The starting items line is missing or holds something other than a list of numbers
+    #[error("Invalid starting items for monkey {monkey}: {line:?}")]
+    BadItems {
+        monkey: usize,
+        line: String,
+        source: Option<Box<ParseError>>,
+    },
+    /// The operation line is missing or isn't of the form `new = <expr> <op> <expr>`
+    #[error("Invalid operation for monkey {monkey}: {line:?}")]
+    BadOperation {
+        monkey: usize,
+        line: String,
+        source: Option<Box<ParseError>>,
+    },
+    /// The divisibility test line is missing or isn't of the form `divisible by N` or
+    /// `new mod N == 0`
+    #[error("Invalid or missing test for monkey {monkey}: {line:?}")]
+    MissingTest {
+        monkey: usize,
+        line: String,
+        source: Option<Box<ParseError>>,
+    },
+    /// One of the if true / if false target lines is missing or malformed
+    #[error("Invalid or missing target for monkey {monkey}: {line:?}")]
+    MissingTarget {
+        monkey: usize,
+        line: String,
+        source: Option<Box<ParseError>>,
+    },
+    /// A monkey throws to a monkey that doesn't exist
+    #[error("Monkey {monkey} throws to monkey {target}, which doesn't exist")]
+    InvalidTarget { monkey: usize, target: usize },
+    /// A monkey throws to itself
+    #[error("Monkey {monkey} throws to itself")]
+    SelfThrow { monkey: usize },
+    /// More than one monkey has the same index
+    #[error("Monkey {monkey} is defined more than once")]
+    DuplicateMonkey { monkey: usize },
+    /// No monkey has this index, but a monkey with a higher index exists
+    #[error("Monkey {monkey} is missing")]
+    MissingMonkey { monkey: usize },
+    /// The divisibility test divides by zero
+    #[error("Monkey {monkey} tests for divisibility by zero")]
+    ZeroDivisor { monkey: usize },
+    /// The least common multiple of the monkeys' divisors doesn't fit in the worry type
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{validate, MonkeyTroop, ParseError};
+    ///
+    /// let input = include_str!("../example-input.txt")
+    ///     .replace("divisible by 23", "divisible by 4294967291")
+    ///     .replace("divisible by 19", "divisible by 4294967279")
+    ///     .replace("divisible by 13", "divisible by 4294967231");
+    ///
+    /// assert!(matches!(validate(&input), Err(ParseError::ModulusOverflow)));
+    /// assert!(input.parse::<MonkeyTroop<u128>>().is_ok());
+    /// # }
+    /// ```
+    #[error("The monkeys' divisors have no common multiple that fits in the worry type")]
+    ModulusOverflow,
+    /// There are fewer than the two monkeys needed to work out the monkey business
+    #[error("Found {found} monkeys, but at least 2 are needed")]
+    TooFewMonkeys { found: usize },
+    /// An operation uses an operator other than `*`, `+`, `-`, `/` or `^`
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{Monkey, ParseError};
+    ///
+    /// let error = Monkey::new(concat!(
+    ///     "Monkey 0:\n",
+    ///     "  Starting items: 79, 98\n",
+    ///     "  Operation: new = old % 3\n",
+    ///     "  Test: divisible by 23\n",
+    ///     "    If true: throw to monkey 2\n",
+    ///     "    If false: throw to monkey 3"
+    /// ))
+    /// .unwrap_err();
+    ///
+    /// let ParseError::BadOperation { monkey: 0, source: Some(source), .. } = error else {
+    ///     panic!("The operation should be rejected");
+    /// };
+    /// assert!(matches!(*source, ParseError::UnknownOperator(ref op) if op == "%"));
+    /// assert!(source.to_string().contains('%'));
+    /// # }
+    /// ```
+    #[error("Unknown operator {0:?}")]
+    UnknownOperator(String),
+    /// Something that should be a number isn't one
+    #[error("Invalid number")]
+    BadNumber(#[from] ParseIntError),
+}
+
+/// Boxes the cause of a malformed line, to be the source of the error reporting the line
+#[cfg(feature = "std")]
+fn cause(e: impl Into<ParseError>) -> Option<Box<ParseError>> {
+    Some(Box::new(e.into()))
+}
+
+/// Parses worry levels separated by commas, whitespace or both, as in either format of a monkey's
+/// starting items
+#[cfg(feature = "std")]
+fn parse_items<T: Worry>(items: &str) -> Result<VecDeque<T>, Option<Box<ParseError>>> {
+    items
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.parse::<T>().map_err(cause))
+        .collect()
+}
+
+/// An error encountered while applying a monkey's operation
+#[derive(Debug, Error)]
+pub enum ArithmeticError {
+    /// The operation divided by zero
+    #[error("Attempted to divide by zero")]
+    DivideByZero,
+    /// The operation overflowed the worry type while using checked arithmetic
+    #[error("Arithmetic overflow")]
+    Overflow,
+    /// The operation raised a number to a negative power
+    #[error("Attempted to raise to a negative power")]
+    NegativeExponent,
+}
+
+/// An error encountered while running the simulation
+#[derive(Debug, Error)]
+pub enum RunError {
+    /// The input couldn't be read
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The input couldn't be parsed
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// A monkey's operation couldn't be applied
+    #[error(transparent)]
+    Arithmetic(#[from] ArithmeticError),
+}
+
+/// An item thrown by a monkey after inspecting it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThrownItem<T = Item> {
+    /// The item's worry level before the monkey's operation
+    pub was: T,
+    /// The item's worry level after the monkey's operation, but before the monkey got bored with it
+    pub became: T,
+    /// The item's worry level after inspection, which decided where it was thrown
+    pub item: T,
+    /// The monkey the item was thrown to
+    pub to_monkey: usize,
+}
+
+/// A level of monkey business, the product of the inspection counts of the two busiest monkeys
+/// # Examples
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::{monkey_business, MonkeyBusiness};
+///
+/// let answer = monkey_business(include_str!("../example-input.txt"), 10000).unwrap();
+///
+/// assert_eq!(MonkeyBusiness(2713310158), answer);
+/// assert_eq!(2713310158, answer.value());
+/// assert_eq!("The level of monkey business is 2713310158", answer.to_string());
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MonkeyBusiness(pub usize);
+
+impl MonkeyBusiness {
+    /// The product of the inspection counts
+    pub fn value(self) -> usize {
+        self.0
+    }
+}
+
+impl From<usize> for MonkeyBusiness {
+    fn from(value: usize) -> Self {
+        MonkeyBusiness(value)
+    }
+}
+
+impl PartialEq<usize> for MonkeyBusiness {
+    fn eq(&self, other: &usize) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<MonkeyBusiness> for usize {
+    fn eq(&self, other: &MonkeyBusiness) -> bool {
+        *self == other.0
+    }
+}
+
+impl fmt::Display for MonkeyBusiness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The level of monkey business is {}", self.0)
+    }
+}
+
+/// How a run with an inspection budget, started by `MonkeyTroop::run_bounded`, ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetOutcome {
+    /// The budget ran out at the end of a round, after `rounds` complete rounds
+    RoundBoundary { rounds: u64 },
+    /// The budget ran out part way through a round, after `rounds` complete rounds, when
+    /// `monkey` was next to inspect an item
+    MidRound { rounds: u64, monkey: usize },
+    /// A whole round passed without any inspections, so the budget could never run out, after
+    /// `rounds` complete rounds
+    Idle { rounds: u64 },
+}
+
+/// A read-only view of a monkey in a `MonkeyTroop`, created by `MonkeyTroop::iter`
+#[derive(Clone, Copy, Debug)]
+pub struct MonkeyView<'a, T = Item> {
+    /// The items the monkey is holding, in the order they will be inspected
+    pub items: &'a VecDeque<T>,
+    /// The divisor used to decide where to throw each item
+    pub test_mod: T,
+    /// The monkey items are thrown to when the test passes
+    pub true_target: usize,
+    /// The monkey items are thrown to when the test fails
+    pub false_target: usize,
+    /// How many times the monkey has inspected an item so far
+    pub inspections: u32,
+}
+
+/// Everything a report needs about a troop, created by `MonkeyTroop::result`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RunResult<T = Item> {
+    /// The monkeys, in index order
+    pub monkeys: Vec<MonkeyResult<T>>,
+}
+
+/// A monkey in a `RunResult`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MonkeyResult<T = Item> {
+    /// The monkey's index
+    pub index: usize,
+    /// How many times the monkey has inspected an item
+    pub inspections: u32,
+    /// The items the monkey is holding, in the order they will be inspected
+    pub items: Vec<T>,
+    /// The divisor used to decide where to throw each item
+    pub divisor: T,
+    /// The monkey items are thrown to when the test passes
+    pub true_target: usize,
+    /// The monkey items are thrown to when the test fails
+    pub false_target: usize,
 }
 
-struct Monkey {
-    items: Vec<Item>,
-    operation: Box<dyn Fn(Item) -> Item>,
-    test_mod: usize,
-    num_inspections: u32,
+/// A single monkey, holding items and deciding where to throw them
+#[derive(Clone, Debug)]
+pub struct Monkey<T = Item> {
+    index: usize,
+    items: VecDeque<T>,
+    operation: Operation<T>,
+    test_mod: T,
     true_monkey_index: usize,
     false_monkey_index: usize,
+    fast_op: Option<FastOp<T>>,
+    custom_op: Option<CustomOp<T>>,
 }
 
 impl Monkey {
-    pub fn new(instring: &str) -> Result<Self, sscanf::Error> {
-        let lines: Vec<_> = instring.lines().collect();
-        let items_str =
-            sscanf!(lines[1], "  Starting items: {str}").expect("There should be an items list");
-        let (expr1, op, expr2) = sscanf!(lines[2], "  Operation: new = {Expr} {Op} {Expr}")
-            .expect("There should be an operation");
-        let test_mod =
-            sscanf!(lines[3], "  Test: divisible by {usize}").expect("There should be a test");
-        let true_monkey_index = sscanf!(lines[4], "    If true: throw to monkey {usize}")
-            .expect("There should be a true monkey");
-        let false_monkey_index = sscanf!(lines[5], "    If false: throw to monkey {usize}")
-            .expect("There should be a false monkey");
+    /// Parses a monkey from its block of the puzzle input
+    /// # Errors
+    /// Returns a `ParseError` if any line of the block is missing or malformed, reporting the
+    /// block as monkey 0
+    #[cfg(feature = "std")]
+    pub fn new(instring: &str) -> Result<Self, ParseError> {
+        Self::from_lines(&instring.lines().collect::<Vec<_>>())
+    }
+
+    /// Parses a monkey from the lines of its block of the puzzle input, ignoring blank lines
+    /// # Errors
+    /// Returns a `ParseError` if any line is missing or malformed, reporting the block as monkey 0
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::Monkey;
+    ///
+    /// let lines = [
+    ///     "Monkey 0:",
+    ///     "  Starting items: 79, 98",
+    ///     "  Operation: new = old * 19",
+    ///     "  Test: divisible by 23",
+    ///     "    If true: throw to monkey 2",
+    ///     "    If false: throw to monkey 3",
+    /// ];
+    ///
+    /// assert_eq!(
+    ///     format!("{:?}", Monkey::new(&lines.join("\n")).unwrap()),
+    ///     format!("{:?}", Monkey::from_lines(&lines).unwrap())
+    /// );
+    /// assert!(Monkey::from_lines(&lines[..5]).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_lines(lines: &[&str]) -> Result<Self, ParseError> {
+        Self::parse_lines(0, lines)
+    }
+
+    /// Parses the first monkey in `input`, returning it along with the rest of the input after
+    /// the blank lines that follow its block
+    /// # Errors
+    /// Returns a `ParseError` if the first block is missing or malformed, reporting it as monkey 0
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::Monkey;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let (first, rest) = Monkey::parse_one(input).unwrap();
+    ///
+    /// let first_block = input.split("\n\n").next().unwrap();
+    /// assert_eq!(
+    ///     format!("{:?}", Monkey::new(first_block).unwrap()),
+    ///     format!("{first:?}")
+    /// );
+    /// assert!(rest.starts_with("Monkey 1:"));
+    ///
+    /// let mut rest = input;
+    /// let mut monkeys = 0;
+    /// while !rest.is_empty() {
+    ///     rest = Monkey::parse_one(rest).unwrap().1;
+    ///     monkeys += 1;
+    /// }
+    /// assert_eq!(4, monkeys);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn parse_one(input: &str) -> Result<(Self, &str), ParseError> {
+        let mut block_end = input.len();
+        let mut offset = 0;
+        let mut started = false;
+        for line in input.split_inclusive('\n') {
+            match line.trim().is_empty() {
+                true if started => {
+                    block_end = offset;
+                    break;
+                }
+                true => (),
+                false => started = true,
+            }
+            offset += line.len();
+        }
+
+        let (block, rest) = input.split_at(block_end);
+        Ok((Self::parse_block(0, block)?, rest.trim_start()))
+    }
+
+    /// Parses a monkey from the compact single-line form `0: [79,98] old*19 %23 ?2:3`, which
+    /// gives the index, starting items, operation, test divisor and the if true / if false
+    /// targets of the verbose block in that order. The operation is written without spaces, and a
+    /// lone operand is taken as adding zero to it, as in `new = old`
+    /// # Errors
+    /// Returns a `ParseError` if any part of the line is missing or malformed
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::{Monkey, ParseError};
+    ///
+    /// let verbose = Monkey::new(
+    ///     "Monkey 0:
+    ///   Starting items: 79, 98
+    ///   Operation: new = old * 19
+    ///   Test: divisible by 23
+    ///     If true: throw to monkey 2
+    ///     If false: throw to monkey 3",
+    /// )
+    /// .unwrap();
+    /// let compact = Monkey::parse_compact("0: [79,98] old*19 %23 ?2:3").unwrap();
+    /// assert_eq!(format!("{verbose:?}"), format!("{compact:?}"));
+    ///
+    /// let verbose = Monkey::new(
+    ///     "Monkey 2:
+    ///   Starting items:
+    ///   Operation: new = old * old
+    ///   Test: divisible by 13
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 3",
+    /// )
+    /// .unwrap();
+    /// let compact = Monkey::parse_compact("2: [] old*old %13 ?1:3").unwrap();
+    /// assert_eq!(format!("{verbose:?}"), format!("{compact:?}"));
+    ///
+    /// assert!(matches!(
+    ///     Monkey::parse_compact("1: [54,65] old+6 %0 ?2:0"),
+    ///     Err(ParseError::ZeroDivisor { monkey: 1 })
+    /// ));
+    /// assert!(matches!(
+    ///     Monkey::parse_compact("1: [54,65] old+6 %19"),
+    ///     Err(ParseError::MissingTarget { monkey: 1, .. })
+    /// ));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn parse_compact(line: &str) -> Result<Self, ParseError> {
+        let (index, rest) = line
+            .split_once(':')
+            .and_then(|(index, rest)| Some((index.trim().parse().ok()?, rest.trim_start())))
+            .ok_or_else(|| ParseError::BadHeader {
+                monkey: 0,
+                line: line.to_string(),
+            })?;
+
+        let (items, rest) = rest
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .ok_or(None)
+            .and_then(|(items_str, rest)| Ok((parse_items(items_str)?, rest)))
+            .map_err(|source| ParseError::BadItems {
+                monkey: index,
+                line: line.to_string(),
+                source,
+            })?;
+        let mut fields = rest.split_whitespace();
+
+        let operation = fields
+            .next()
+            .ok_or(None)
+            .and_then(|o| {
+                // The operator is the first operator character after the start of the left operand
+                match o.find(['*', '+', '-', '/', '^']).filter(|&at| at > 0) {
+                    Some(at) => Operation::parse(&o[..at], &o[at..=at], &o[at + 1..]),
+                    None => Operation::parse(o, "+", "0"),
+                }
+                .map_err(cause)
+            })
+            .map_err(|source| ParseError::BadOperation {
+                monkey: index,
+                line: line.to_string(),
+                source,
+            })?;
+
+        let test_mod: Item = fields
+            .next()
+            .and_then(|t| t.strip_prefix('%'))
+            .ok_or(None)
+            .and_then(|n| n.parse().map_err(cause))
+            .map_err(|source| ParseError::MissingTest {
+                monkey: index,
+                line: line.to_string(),
+                source,
+            })?;
+        if test_mod == 0 {
+            return Err(ParseError::ZeroDivisor { monkey: index });
+        }
+
+        let (true_monkey_index, false_monkey_index) = fields
+            .next()
+            .and_then(|t| t.strip_prefix('?')?.split_once(':'))
+            .ok_or(None)
+            .and_then(|(t, f)| Ok((t.parse().map_err(cause)?, f.parse().map_err(cause)?)))
+            .map_err(|source| ParseError::MissingTarget {
+                monkey: index,
+                line: line.to_string(),
+                source,
+            })?;
 
         Ok(Monkey {
-            items: items_str
-                .split(", ")
-                .map(str::parse::<Item>)
-                .map(Result::unwrap)
-                .collect(),
-            operation: Box::new(move |old: Item| op.on(expr1.or(old), expr2.or(old))),
+            index,
+            items,
+            operation,
+            test_mod,
+            true_monkey_index,
+            false_monkey_index,
+            fast_op: None,
+            custom_op: None,
+        })
+    }
+
+    /// Starts building a monkey programmatically, as in the example on `MonkeyBuilder`
+    pub fn builder() -> MonkeyBuilder {
+        MonkeyBuilder::default()
+    }
+
+    /// Creates a monkey whose operation is the closure `op` rather than anything the puzzle input
+    /// can express. `op` is given worry levels below the troop's modulus, starting items included,
+    /// and its result is reduced by the modulus in turn, so it should only use arithmetic that's
+    /// compatible with that, such as addition and multiplication. Without a modulus, as with a
+    /// worry divisor, `op` is given the raw worry levels. It's also left to `op` to avoid
+    /// overflowing, even with checked arithmetic, and a troop with such a monkey can't be
+    /// snapshotted
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{Monkey, MonkeyTroop};
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut monkeys: Vec<_> = input
+    ///     .split("\n\n")
+    ///     .filter(|block| !block.trim().is_empty())
+    ///     .map(|block| Monkey::new(block).unwrap())
+    ///     .collect();
+    /// monkeys[2] = Monkey::with_operation([79, 60, 97], Box::new(|old| old.pow(3)), 13, 1, 3);
+    ///
+    /// let mut custom = MonkeyTroop::from_monkeys(monkeys).unwrap();
+    /// let mut parsed = MonkeyTroop::parse(&input.replace("old * old", "old ^ 3")).unwrap();
+    ///
+    /// custom.run(1000).unwrap();
+    /// parsed.run(1000).unwrap();
+    /// assert_eq!(parsed.inspection_counts(), custom.inspection_counts());
+    /// assert_eq!(parsed.to_string(), custom.to_string());
+    ///
+    /// // 10000000 is reduced modulo 77 before it's cubed, so it can't overflow
+    /// let monkeys = vec![
+    ///     Monkey::with_operation([10_000_000], Box::new(|old| old.pow(3)), 7, 1, 1),
+    ///     Monkey::builder().test_divisor(11).targets(0, 0).build(),
+    /// ];
+    /// let mut troop = MonkeyTroop::from_monkeys(monkeys).unwrap();
+    /// troop.step_round().unwrap();
+    /// assert_eq!(vec![vec![76], vec![]], troop.held_items());
+    /// # }
+    /// ```
+    pub fn with_operation(
+        items: impl IntoIterator<Item = Item>,
+        op: Box<dyn Fn(Item) -> Item + Send + Sync>,
+        test_mod: Item,
+        true_monkey_index: usize,
+        false_monkey_index: usize,
+    ) -> Self {
+        Monkey {
+            custom_op: Some(CustomOp(Arc::from(op))),
+            ..Self::builder()
+                .items(items)
+                .test_divisor(test_mod)
+                .targets(true_monkey_index, false_monkey_index)
+                .build()
+        }
+    }
+}
+
+impl<T: Worry> Monkey<T> {
+    /// Parses the `monkey`th block of the puzzle input
+    #[cfg(feature = "std")]
+    fn parse_block(monkey: usize, instring: &str) -> Result<Self, ParseError> {
+        Self::parse_lines(monkey, &instring.lines().collect::<Vec<_>>())
+    }
+
+    /// Parses the lines of the `monkey`th block of the puzzle input
+    #[cfg(feature = "std")]
+    fn parse_lines(monkey: usize, lines: &[&str]) -> Result<Self, ParseError> {
+        let lines: Vec<_> = lines
+            .iter()
+            .copied()
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+        // Finds the line starting with `prefix`, returning it along with whatever follows the prefix
+        let field = |prefix: &str| {
+            let line = lines
+                .iter()
+                .copied()
+                .find(|l| l.trim_start().starts_with(prefix))
+                .unwrap_or_default();
+            (line, line.trim().strip_prefix(prefix).map(str::trim))
+        };
+
+        let header = lines.first().copied().unwrap_or_default();
+        let index =
+            sscanf!(header.trim(), "Monkey {usize}:").map_err(|_| ParseError::BadHeader {
+                monkey,
+                line: header.to_string(),
+            })?;
+
+        let (line, items_str) = field("Starting items:");
+        let items = items_str
+            .ok_or(None)
+            .and_then(parse_items)
+            .map_err(|source| ParseError::BadItems {
+                monkey,
+                line: line.to_string(),
+                source,
+            })?;
+
+        let (line, operation) = field("Operation:");
+        let mut operation = operation
+            .and_then(|o| {
+                sscanf!(o, "new = {str} {str} {str}").ok().or_else(|| {
+                    // A lone operand, as in `new = old`, is taken as adding zero to it
+                    let operand = sscanf!(o, "new = {str}").ok()?;
+                    (!operand.contains(char::is_whitespace)).then_some((operand, "+", "0"))
+                })
+            })
+            .ok_or(None)
+            .and_then(|(lhs, op, rhs)| Operation::parse(lhs, op, rhs).map_err(cause))
+            .map_err(|source| ParseError::BadOperation {
+                monkey,
+                line: line.to_string(),
+                source,
+            })?;
+
+        if let (line, Some(modulo)) = field("Operation mod:") {
+            operation.modulo = modulo
+                .parse::<T>()
+                .map_err(cause)
+                .and_then(|m| match m.is_zero() {
+                    true => Err(None),
+                    false => Ok(Some(m)),
+                })
+                .map_err(|source| ParseError::BadOperation {
+                    monkey,
+                    line: line.to_string(),
+                    source,
+                })?;
+        }
+
+        let (line, test) = field("Test:");
+        let test_mod: T = test
+            .and_then(|t| {
+                sscanf!(t, "divisible by {str}")
+                    .or_else(|_| sscanf!(t, "new mod {str} == 0"))
+                    .ok()
+            })
+            .ok_or(None)
+            .and_then(|n| n.parse().map_err(cause))
+            .map_err(|source| ParseError::MissingTest {
+                monkey,
+                line: line.to_string(),
+                source,
+            })?;
+        if test_mod.is_zero() {
+            return Err(ParseError::ZeroDivisor { monkey });
+        }
+
+        let target = |prefix: &str| {
+            let (line, target) = field(prefix);
+            target
+                .and_then(|t| sscanf!(t, "throw to monkey {str}").ok())
+                .ok_or(None)
+                .and_then(|n| n.parse().map_err(cause))
+                .map_err(|source| ParseError::MissingTarget {
+                    monkey,
+                    line: line.to_string(),
+                    source,
+                })
+        };
+        let true_monkey_index = target("If true:")?;
+        let false_monkey_index = target("If false:")?;
+
+        Ok(Monkey {
+            index,
+            items,
+            operation,
             test_mod,
-            num_inspections: 0,
             true_monkey_index,
             false_monkey_index,
+            fast_op: None,
+            custom_op: None,
         })
     }
 
-    pub fn inspect_next(&mut self, modulo: usize) -> Option<ThrownItem> {
-        let old = self.items.pop()?;
-        let new = (self.operation)(old) % modulo;
+    /// Inspects the next item, if there is one, applying the monkey's operation modulo `modulo`
+    /// followed by `reduce`, and decides where to throw it. `round` is the number of the round
+    /// being played, for operations that refer to it. With `checked`, the operation fails when an
+    /// intermediate result doesn't fit in the worry type
+    /// # Errors
+    /// Returns an `ArithmeticError` if the monkey's operation fails, leaving the item with the
+    /// monkey
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::{Expr, Monkey, Op};
+    ///
+    /// let inspect = |old: u64, times: u64, modulo: u64| {
+    ///     let mut monkey = Monkey::builder()
+    ///         .items([old])
+    ///         .operation(Expr::Old, Op::Times, Expr::Num(times))
+    ///         .build();
+    ///     monkey.inspect_next(modulo, 1, false, |x| x).unwrap().unwrap().item
+    /// };
+    ///
+    /// // Products beyond `u64::MAX` are reduced exactly
+    /// let max = u64::MAX;
+    /// assert_eq!(6743105841750238095, inspect(12345678901234567890, 9876543210987654321, max));
+    /// assert_eq!(1, inspect(max - 1, max - 1, max));
+    /// assert_eq!(max / 2, inspect(max - 1, 1 << 63, max));
+    /// assert_eq!(13835058055282164927, inspect((1 << 63) + 5, (1 << 63) + 7, max - 58));
+    /// ```
+    pub fn inspect_next(
+        &mut self,
+        modulo: T,
+        round: T,
+        checked: bool,
+        reduce: impl Fn(T) -> T,
+    ) -> Result<Option<ThrownItem<T>>, ArithmeticError> {
+        let thrown = self.peek_next(modulo, round, checked, reduce)?;
+        if thrown.is_some() {
+            self.items.pop_front();
+        }
+        Ok(thrown)
+    }
+
+    /// Works out what `inspect_next` would throw, without removing the item from the monkey
+    /// # Errors
+    /// Returns an `ArithmeticError` if the monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{Monkey, ThrownItem};
+    ///
+    /// let mut monkey = Monkey::new(concat!(
+    ///     "Monkey 0:\n",
+    ///     "  Starting items: 79, 98\n",
+    ///     "  Operation: new = old * 19\n",
+    ///     "  Test: divisible by 23\n",
+    ///     "    If true: throw to monkey 2\n",
+    ///     "    If false: throw to monkey 3"
+    /// ))
+    /// .unwrap();
+    /// let expected = Some(ThrownItem {
+    ///     was: 79,
+    ///     became: 1501,
+    ///     item: 500,
+    ///     to_monkey: 3,
+    /// });
+    ///
+    /// assert_eq!(expected, monkey.peek_next(96577, 1, false, |x| x / 3).unwrap());
+    /// assert_eq!(expected, monkey.peek_next(96577, 1, false, |x| x / 3).unwrap());
+    /// assert_eq!(expected, monkey.inspect_next(96577, 1, false, |x| x / 3).unwrap());
+    /// assert_ne!(expected, monkey.peek_next(96577, 1, false, |x| x / 3).unwrap());
+    /// # }
+    /// ```
+    pub fn peek_next(
+        &self,
+        modulo: T,
+        round: T,
+        checked: bool,
+        reduce: impl Fn(T) -> T,
+    ) -> Result<Option<ThrownItem<T>>, ArithmeticError> {
+        self.items
+            .front()
+            .map(|&old| self.inspect(old, modulo, round, checked, reduce))
+            .transpose()
+    }
 
-        self.num_inspections += 1;
+    /// Applies the monkey's operation and `reduce` to an item's worry level `old`, and decides
+    /// where to throw it
+    fn inspect(
+        &self,
+        old: T,
+        modulo: T,
+        round: T,
+        checked: bool,
+        reduce: impl Fn(T) -> T,
+    ) -> Result<ThrownItem<T>, ArithmeticError> {
+        let became = match (&self.custom_op, self.fast_op) {
+            (Some(custom_op), _) => (custom_op.0)(old % modulo) % modulo,
+            // A worry level that hasn't been reduced yet could overflow the fast operation
+            (None, Some(fast_op)) if fast_op.modulo == modulo && old < modulo => fast_op.apply(old),
+            (None, _) => self.operation.apply(old, round, modulo, checked)?,
+        };
+        let new = reduce(became) % modulo;
 
-        Some(ThrownItem {
+        Ok(ThrownItem {
+            was: old,
+            became,
             item: new,
-            to_monkey: match new % self.test_mod == 0 {
+            to_monkey: match new.is_multiple_of(&self.test_mod) {
                 true => self.true_monkey_index,
                 false => self.false_monkey_index,
             },
         })
     }
 
-    pub fn catch(&mut self, item: Item) {
-        self.items.push(item);
+    /// Caches the monkey's operation specialised for `modulo`, which `inspect_next` uses whenever
+    /// it's given the same modulus
+    fn prepare(&mut self, modulo: T) {
+        self.fast_op = FastOp::new(&self.operation, modulo);
+    }
+
+    /// Receives an item thrown by another monkey
+    pub fn catch(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Worry> FromStr for Monkey<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_block(0, s)
     }
 }
 
-/// Calculates the level of monkey business and returns it
+/// Builds a `Monkey` without going through the text format, created by `Monkey::builder` or, for
+/// other worry types, `MonkeyBuilder::default`. By default the monkey holds no items, leaves worry
+/// unchanged, tests divisibility by 1 and throws everything to monkey 0
+///
+/// Without the default `std` feature, the crate is `no_std` and can't parse the puzzle input, so
+/// building monkeys is the way to make a troop
 /// # Examples
 /// ```
-/// use advent_of_code_2022_11::monkey_business;
+/// use advent_of_code_2022_11::{Expr, Monkey, MonkeyTroop, Op};
 ///
-/// assert_eq!(
-///     2713310158,
-///     monkey_business(concat!(
-///         "Monkey 0:\n",
-///         "  Starting items: 79, 98\n",
-///         "  Operation: new = old * 19\n",
-///         "  Test: divisible by 23\n",
-///         "    If true: throw to monkey 2\n",
-///         "    If false: throw to monkey 3\n",
-///         "\n",
-///         "Monkey 1:\n",
-///         "  Starting items: 54, 65, 75, 74\n",
-///         "  Operation: new = old + 6\n",
-///         "  Test: divisible by 19\n",
-///         "    If true: throw to monkey 2\n",
-///         "    If false: throw to monkey 0\n",
-///         "\n",
-///         "Monkey 2:\n",
-///         "  Starting items: 79, 60, 97\n",
-///         "  Operation: new = old * old\n",
-///         "  Test: divisible by 13\n",
-///         "    If true: throw to monkey 1\n",
-///         "    If false: throw to monkey 3\n",
-///         "\n",
-///         "Monkey 3:\n",
-///         "  Starting items: 74\n",
-///         "  Operation: new = old + 3\n",
-///         "  Test: divisible by 17\n",
-///         "    If true: throw to monkey 0\n",
-///         "    If false: throw to monkey 1"
-/// ), 10000));
-/// ```
-pub fn monkey_business(input: &str, n_rounds: u32) -> usize {
-    let mut monkeys: Vec<Monkey> = input
-        .split("\n\n")
-        .filter(|s| !s.is_empty())
-        .map(Monkey::new)
-        .map(|m| m.expect("This should produce a valid Monkey"))
-        .collect();
-
-    let modulo: usize = monkeys.iter().map(|m| m.test_mod).product();
-
-    for _ in 0..n_rounds {
-        for i in 0..monkeys.len() {
-            let (left, big_right) = monkeys.split_at_mut(i);
-            let (monkey, right) = big_right.split_at_mut(1);
-            let mut other_monkey: &mut Monkey;
-            while let Some(ThrownItem { item, to_monkey }) = monkey[0].inspect_next(modulo) {
-                if to_monkey < i {
-                    other_monkey = &mut left[to_monkey]
-                } else {
-                    other_monkey = &mut right[to_monkey - (i + 1)]
-                }
+/// let monkeys = vec![
+///     Monkey::builder()
+///         .items([79, 98])
+///         .operation(Expr::Old, Op::Times, Expr::Num(19))
+///         .test_divisor(23)
+///         .targets(2, 3)
+///         .build(),
+///     Monkey::builder()
+///         .items([54, 65, 75, 74])
+///         .operation(Expr::Old, Op::Plus, Expr::Num(6))
+///         .test_divisor(19)
+///         .targets(2, 0)
+///         .build(),
+///     Monkey::builder()
+///         .items([79, 60, 97])
+///         .operation(Expr::Old, Op::Times, Expr::Old)
+///         .test_divisor(13)
+///         .targets(1, 3)
+///         .build(),
+///     Monkey::builder()
+///         .items([74])
+///         .operation(Expr::Old, Op::Plus, Expr::Num(3))
+///         .test_divisor(17)
+///         .targets(0, 1)
+///         .build(),
+/// ];
+/// let mut troop = MonkeyTroop::from_monkeys(monkeys).unwrap();
+///
+/// troop.run(10000).unwrap();
+/// assert_eq!(2713310158, troop.monkey_business());
+/// ```
+pub struct MonkeyBuilder<T = Item> {
+    items: VecDeque<T>,
+    operation: Operation<T>,
+    test_mod: T,
+    true_monkey_index: usize,
+    false_monkey_index: usize,
+}
 
-                other_monkey.catch(item);
-            }
+impl<T: Worry> Default for MonkeyBuilder<T> {
+    fn default() -> Self {
+        MonkeyBuilder {
+            items: VecDeque::new(),
+            operation: Operation {
+                lhs: Expr::Old,
+                op: Op::Plus,
+                rhs: Expr::Num(T::zero()),
+                modulo: None,
+            },
+            test_mod: T::one(),
+            true_monkey_index: 0,
+            false_monkey_index: 0,
+        }
+    }
+}
+
+impl<T: Worry> MonkeyBuilder<T> {
+    /// Sets the items the monkey starts with, in the order they will be inspected
+    pub fn items(mut self, items: impl IntoIterator<Item = T>) -> Self {
+        self.items = items.into_iter().collect();
+        self
+    }
+
+    /// Sets the operation to `new = lhs op rhs`
+    pub fn operation(mut self, lhs: Expr<T>, op: Op, rhs: Expr<T>) -> Self {
+        self.operation = Operation {
+            lhs,
+            op,
+            rhs,
+            modulo: None,
+        };
+        self
+    }
+
+    /// Sets the divisor used to decide where to throw each item
+    pub fn test_divisor(mut self, test_mod: T) -> Self {
+        self.test_mod = test_mod;
+        self
+    }
+
+    /// Sets the monkeys to throw to when the test passes and fails respectively
+    pub fn targets(mut self, if_true: usize, if_false: usize) -> Self {
+        self.true_monkey_index = if_true;
+        self.false_monkey_index = if_false;
+        self
+    }
+
+    /// Builds the monkey
+    pub fn build(self) -> Monkey<T> {
+        Monkey {
+            index: 0,
+            items: self.items,
+            operation: self.operation,
+            test_mod: self.test_mod,
+            true_monkey_index: self.true_monkey_index,
+            false_monkey_index: self.false_monkey_index,
+            fast_op: None,
+            custom_op: None,
         }
     }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct MonkeySnapshot<T> {
+    items: Vec<T>,
+    inspections: u32,
+    operation: Operation<T>,
+    test_mod: T,
+    true_monkey_index: usize,
+    false_monkey_index: usize,
+}
+
+/// The state of a `MonkeyTroop` part way through a simulation, which can be serialized with the
+/// `serde` feature and later resumed with `MonkeyTroop::from_snapshot`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TroopSnapshot<T = Item> {
+    monkeys: Vec<MonkeySnapshot<T>>,
+    worry_divisor: T,
+    checked: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    rounds: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    next_monkey: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    unbounded: bool,
+}
+
+/// A troop of monkeys playing keep away, which can be run a few rounds at a time
+/// # Examples
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+///
+/// troop.step_round().unwrap();
+/// assert_eq!(&[2, 4, 3, 6], troop.inspection_counts());
+///
+/// troop.run(19).unwrap();
+/// assert_eq!(&[99, 97, 8, 103], troop.inspection_counts());
+/// assert_eq!(10197, troop.monkey_business());
+/// # }
+/// ```
+///
+/// A parsed troop can be cloned to run Part 1 and Part 2 without parsing twice
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let mut part2 = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+/// let mut part1 = part2.clone().with_worry_divisor(3);
+///
+/// part1.run(20).unwrap();
+/// part2.run(10000).unwrap();
+/// assert_eq!(10605, part1.monkey_business());
+/// assert_eq!(2713310158, part2.monkey_business());
+/// # }
+/// ```
+///
+/// or to try out a change without affecting the original
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let original = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+/// let mut what_if = original.clone();
+/// what_if.reset_items(vec![vec![1, 2, 3], vec![54, 65, 75, 74], vec![79, 60, 97], vec![74]]);
+/// what_if.run(20).unwrap();
+///
+/// assert_eq!(&[0, 0, 0, 0], original.inspection_counts());
+/// assert!(original.to_string().starts_with("Monkey 0: 79, 98\n"));
+/// assert_ne!(original.inspection_counts(), what_if.inspection_counts());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MonkeyTroop<T = Item> {
+    monkeys: Vec<Monkey<T>>,
+    inspections: Vec<u32>,
+    modulo: T,
+    /// Whether every operation carries over to remainders, so the modulus can be used at all
+    modular: bool,
+    worry_divisor: T,
+    checked: bool,
+    rounds_elapsed: u64,
+    /// The monkey whose turn it is in the round being played
+    next_monkey: usize,
+    /// How many more items the monkey whose turn it is should throw, once its turn has begun
+    turn_left: Option<usize>,
+    /// The inspection counts when the round being played began, if the round is being logged
+    #[cfg(feature = "log")]
+    round_start: Option<Vec<u32>>,
+    total_items: usize,
+    start: ResetPoint<T>,
+}
+
+/// The state `MonkeyTroop::reset` rewinds a troop to
+#[derive(Clone, Debug)]
+struct ResetPoint<T> {
+    items: Vec<Vec<T>>,
+    inspections: Vec<u32>,
+    rounds: u64,
+    next_monkey: usize,
+}
+
+impl MonkeyTroop {
+    /// Parses a troop from the puzzle input, storing worry levels as `Item`s. Other worry types
+    /// can be parsed with `str::parse`
+    /// # Errors
+    /// Returns a `ParseError` if any monkey in the input is malformed or throws to itself or to a
+    /// monkey that doesn't exist, or there are fewer than two monkeys
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::{MonkeyTroop, ParseError};
+    ///
+    /// let input = include_str!("../example-input.txt").replace("monkey 3", "monkey 7");
+    ///
+    /// assert!(matches!(
+    ///     MonkeyTroop::parse(&input),
+    ///     Err(ParseError::InvalidTarget { monkey: 0, target: 7 })
+    /// ));
+    ///
+    /// let input = include_str!("../example-input.txt").replace("monkey 3", "monkey 0");
+    ///
+    /// assert!(matches!(
+    ///     MonkeyTroop::parse(&input),
+    ///     Err(ParseError::SelfThrow { monkey: 0 })
+    /// ));
+    /// ```
+    ///
+    /// Errors point at the offending monkey and line
+    /// ```
+    /// use advent_of_code_2022_11::{MonkeyTroop, ParseError};
+    ///
+    /// let input = include_str!("../example-input.txt").replace("old * old", "old % 2");
+    ///
+    /// match MonkeyTroop::parse(&input) {
+    ///     Err(ParseError::BadOperation { monkey, line, .. }) => {
+    ///         assert_eq!(2, monkey);
+    ///         assert_eq!("  Operation: new = old % 2", line);
+    ///     }
+    ///     _ => panic!("The operation should be rejected"),
+    /// }
+    /// ```
+    ///
+    /// Monkeys may start with no items
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt")
+    ///     .replace("Starting items: 74\n", "Starting items:\n");
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         vec![79, 98],
+    ///         vec![54, 65, 75, 74],
+    ///         vec![79, 60, 97],
+    ///         vec![],
+    ///     ],
+    ///     MonkeyTroop::parse(&input).unwrap().held_items()
+    /// );
+    /// ```
+    ///
+    /// Items may be separated by commas, whitespace or both
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let expected = MonkeyTroop::parse(input).unwrap().held_items();
+    ///
+    /// for separated in [
+    ///     input.replace(", ", ","),
+    ///     input.replace(", ", " "),
+    ///     input.replace("79, 98", "79 ,98").replace("54, 65, 75, 74", "54  65,\t75 , 74"),
+    /// ] {
+    ///     assert_eq!(expected, MonkeyTroop::parse(&separated).unwrap().held_items());
+    /// }
+    /// ```
+    ///
+    /// Monkeys may be listed in any order
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut blocks: Vec<_> = input.trim_end().split("\n\n").collect();
+    /// blocks.swap(0, 2);
+    /// blocks.swap(1, 3);
+    /// let shuffled = blocks.join("\n\n");
+    /// let mut troop = MonkeyTroop::parse(&shuffled).unwrap();
+    ///
+    /// troop.run(20).unwrap();
+    /// assert_eq!(&[99, 97, 8, 103], troop.inspection_counts());
+    /// ```
+    ///
+    /// but each index must be used exactly once
+    /// ```
+    /// use advent_of_code_2022_11::{MonkeyTroop, ParseError};
+    ///
+    /// let input = include_str!("../example-input.txt");
+    ///
+    /// assert!(matches!(
+    ///     MonkeyTroop::parse(&input.replace("Monkey 2:", "Monkey 1:")),
+    ///     Err(ParseError::DuplicateMonkey { monkey: 1 })
+    /// ));
+    /// assert!(matches!(
+    ///     MonkeyTroop::parse(&input.replace("Monkey 2:", "Monkey 4:")),
+    ///     Err(ParseError::MissingMonkey { monkey: 2 })
+    /// ));
+    /// ```
+    ///
+    /// With the `rayon` feature, monkeys are parsed in parallel
+    /// ```
+    /// # #[cfg(feature = "rayon")]
+    /// # {
+    /// use advent_of_code_2022_11::{Monkey, MonkeyTroop};
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let blocks = input.trim_end().split("\n\n");
+    /// let serial = blocks.map(Monkey::new).collect::<Result<_, _>>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     format!("{:?}", MonkeyTroop::from_monkeys(serial).unwrap()),
+    ///     format!("{:?}", MonkeyTroop::parse(input).unwrap())
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// Blank lines and whitespace around each line are ignored
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input: Vec<_> = include_str!("../example-input.txt")
+    ///     .lines()
+    ///     .map(|line| match line {
+    ///         "" => String::new(),
+    ///         _ => format!(" {line}\t "),
+    ///     })
+    ///     .collect();
+    /// let input = format!("\n\n{}\n\n \n\t", input.join("\n"));
+    /// let mut troop = MonkeyTroop::parse(&input).unwrap();
+    ///
+    /// troop.run(20).unwrap();
+    /// assert_eq!(&[99, 97, 8, 103], troop.inspection_counts());
+    /// ```
+    ///
+    /// Monkeys may be separated by any number of blank or whitespace-only lines
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut blocks = input.trim_end().split("\n\n");
+    /// let irregular = [
+    ///     blocks.next().unwrap(),
+    ///     "\n\n\n",
+    ///     blocks.next().unwrap(),
+    ///     "\n \n",
+    ///     blocks.next().unwrap(),
+    ///     "\n\t\n  \n\n",
+    ///     blocks.next().unwrap(),
+    /// ]
+    /// .concat();
+    /// let mut troop = MonkeyTroop::parse(&irregular).unwrap();
+    ///
+    /// troop.run(20).unwrap();
+    /// assert_eq!(&[99, 97, 8, 103], troop.inspection_counts());
+    /// ```
+    ///
+    /// An operation can leave the worry level as it is
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = "Monkey 0:
+    ///   Starting items: 4, 5
+    ///   Operation: new = old
+    ///   Test: divisible by 2
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 2
+    ///
+    /// Monkey 1:
+    ///   Starting items:
+    ///   Operation: new = old
+    ///   Test: divisible by 3
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0
+    ///
+    /// Monkey 2:
+    ///   Starting items:
+    ///   Operation: new = old
+    ///   Test: divisible by 5
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0";
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// let throws: Vec<_> = troop
+    ///     .throws(1)
+    ///     .map(|throw| throw.map(|(_, thrown)| (thrown.item, thrown.to_monkey)))
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(vec![(4, 1), (5, 2), (4, 0), (5, 0)], throws);
+    /// ```
+    ///
+    /// Windows line endings are accepted
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut troop = MonkeyTroop::parse(&input.replace('\n', "\r\n")).unwrap();
+    ///
+    /// troop.run(20).unwrap();
+    /// assert_eq!(&[99, 97, 8, 103], troop.inspection_counts());
+    /// ```
+    ///
+    /// The test may also be written as `new mod N == 0`
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let rephrased = input
+    ///     .replace("divisible by 23", "new mod 23 == 0")
+    ///     .replace("divisible by 13", "new mod 13 == 0");
+    /// let mut plain = MonkeyTroop::parse(input).unwrap();
+    /// let mut troop = MonkeyTroop::parse(&rephrased).unwrap();
+    ///
+    /// plain.run(10000).unwrap();
+    /// troop.run(10000).unwrap();
+    /// assert_eq!(plain.inspection_counts(), troop.inspection_counts());
+    /// assert!(MonkeyTroop::parse(&input.replace("divisible by 23", "new mod 23 == 1")).is_err());
+    /// ```
+    ///
+    /// Lines starting with `#` are comments, and are ignored wherever they appear
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let commented = format!("# The sample input\n{input}")
+    ///     .replace("Monkey 1:", "# The second monkey\nMonkey 1:")
+    ///     .replace("\n\nMonkey 2:", "\n\n  # Between monkeys\n\nMonkey 2:")
+    ///     .replace("Monkey 3:\n", "Monkey 3:\n# Inside a monkey\n");
+    /// let mut plain = MonkeyTroop::parse(input).unwrap();
+    /// let mut troop = MonkeyTroop::parse(&commented).unwrap();
+    ///
+    /// plain.run(20).unwrap();
+    /// troop.run(20).unwrap();
+    /// assert_eq!(plain.inspection_counts(), troop.inspection_counts());
+    /// assert_eq!(4, troop.inspection_counts().len());
+    /// ```
+    ///
+    /// A monkey may take the result of its operation modulo some number, given on an
+    /// `Operation mod:` line, independently of its test
+    /// ```
+    /// use advent_of_code_2022_11::{MonkeyTroop, ThrownItem};
+    ///
+    /// let input = "Monkey 0:
+    ///   Starting items: 998
+    ///   Operation: new = old + 5
+    ///   Operation mod: 7
+    ///   Test: divisible by 2
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 1
+    ///
+    /// Monkey 1:
+    ///   Starting items:
+    ///   Operation: new = old * 1
+    ///   Test: divisible by 3
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0";
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// let (_, first) = troop.throws(1).next().unwrap().unwrap();
+    ///
+    /// // 1003 mod 7
+    /// assert_eq!(
+    ///     ThrownItem {
+    ///         was: 998,
+    ///         became: 2,
+    ///         item: 2,
+    ///         to_monkey: 1
+    ///     },
+    ///     first
+    /// );
+    /// assert_eq!(2 * 3 * 7, troop.modulus());
+    ///
+    /// let zero = input.replace("mod: 7", "mod: 0");
+    /// assert!(MonkeyTroop::parse(&zero).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        input.parse()
+    }
+
+    /// Parses a troop from the puzzle input one line at a time, holding no more than one monkey's
+    /// lines in memory at once
+    /// # Errors
+    /// Returns a `RunError` if `reader` can't be read or the input is invalid as for `parse`
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    /// use std::io::Cursor;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut read = MonkeyTroop::from_reader(Cursor::new(input.as_bytes())).unwrap();
+    /// let mut parsed = MonkeyTroop::parse(input).unwrap();
+    ///
+    /// read.run(10000).unwrap();
+    /// parsed.run(10000).unwrap();
+    /// assert_eq!(parsed.inspection_counts(), read.inspection_counts());
+    ///
+    /// let truncated = &input.as_bytes()[..input.len() / 2];
+    /// assert!(MonkeyTroop::from_reader(Cursor::new(truncated)).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_reader(reader: impl io::BufRead) -> Result<Self, RunError> {
+        let mut monkeys = Vec::new();
+        let mut block = Vec::new();
+        // A blank line at the end finishes off the last monkey
+        for line in reader.lines().chain(core::iter::once(Ok(String::new()))) {
+            let line = line?;
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            if !line.trim().is_empty() {
+                block.push(line);
+                continue;
+            }
+            if !block.is_empty() {
+                let lines: Vec<_> = block.iter().map(String::as_str).collect();
+                monkeys.push(Monkey::parse_lines(monkeys.len(), &lines)?);
+                block.clear();
+            }
+        }
+
+        Ok(Self::from_numbered(monkeys)?)
+    }
+}
+
+impl<T: Worry> MonkeyTroop<T> {
+    #[cfg(feature = "std")]
+    fn parse_troop(input: &str) -> Result<Self, ParseError> {
+        let lines: Vec<_> = input
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('#'))
+            .collect();
+        let blocks: Vec<_> = lines
+            .split(|l| l.trim().is_empty())
+            .filter(|block| !block.is_empty())
+            .collect();
+
+        #[cfg(feature = "rayon")]
+        let monkeys: Vec<Monkey<T>> = blocks
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, block)| Monkey::parse_lines(i, block))
+            .collect::<Result<_, _>>()?;
+        #[cfg(not(feature = "rayon"))]
+        let monkeys: Vec<Monkey<T>> = blocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, block)| Monkey::parse_lines(i, block))
+            .collect::<Result<_, _>>()?;
+
+        Self::from_numbered(monkeys)
+    }
+
+    /// Assembles a troop from parsed monkeys in any order, checking that each index is used once
+    #[cfg(feature = "std")]
+    fn from_numbered(mut monkeys: Vec<Monkey<T>>) -> Result<Self, ParseError> {
+        monkeys.sort_by_key(|m| m.index);
+        for (i, monkey) in monkeys.iter().enumerate() {
+            match monkey.index.cmp(&i) {
+                Ordering::Less => {
+                    return Err(ParseError::DuplicateMonkey {
+                        monkey: monkey.index,
+                    })
+                }
+                Ordering::Greater => return Err(ParseError::MissingMonkey { monkey: i }),
+                Ordering::Equal => (),
+            }
+        }
+
+        Self::from_monkeys(monkeys)
+    }
+
+    /// Assembles a troop from monkeys in index order
+    /// # Errors
+    /// Returns a `ParseError` if there are fewer than two monkeys, any monkey tests for
+    /// divisibility by zero or throws to itself or to a monkey that doesn't exist, or the common
+    /// multiple of the divisors doesn't fit in the worry type
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{Monkey, MonkeyTroop, ParseError};
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let first_block = input.split("\n\n").next().unwrap();
+    ///
+    /// assert!(matches!(
+    ///     MonkeyTroop::parse(first_block),
+    ///     Err(ParseError::TooFewMonkeys { found: 1 })
+    /// ));
+    /// assert!(matches!(
+    ///     MonkeyTroop::from_monkeys(Vec::<Monkey>::new()),
+    ///     Err(ParseError::TooFewMonkeys { found: 0 })
+    /// ));
+    /// # }
+    /// ```
+    ///
+    /// Built monkeys are checked in the same way as parsed ones, and make a troop that runs like
+    /// any other
+    /// ```
+    /// use advent_of_code_2022_11::{Expr, Monkey, MonkeyTroop, Op, ParseError};
+    ///
+    /// let pair = |to_first: usize, to_second: usize| {
+    ///     vec![
+    ///         Monkey::builder()
+    ///             .items([1, 2])
+    ///             .operation(Expr::Old, Op::Plus, Expr::Num(1))
+    ///             .test_divisor(2)
+    ///             .targets(to_first, to_first)
+    ///             .build(),
+    ///         Monkey::builder().targets(to_second, to_second).build(),
+    ///     ]
+    /// };
+    ///
+    /// assert!(matches!(
+    ///     MonkeyTroop::from_monkeys(pair(2, 0)),
+    ///     Err(ParseError::InvalidTarget { monkey: 0, target: 2 })
+    /// ));
+    /// assert!(matches!(
+    ///     MonkeyTroop::from_monkeys(pair(1, 1)),
+    ///     Err(ParseError::SelfThrow { monkey: 1 })
+    /// ));
+    ///
+    /// let mut zero = pair(1, 0);
+    /// zero[1] = Monkey::builder().test_divisor(0).build();
+    /// assert!(matches!(
+    ///     MonkeyTroop::from_monkeys(zero),
+    ///     Err(ParseError::ZeroDivisor { monkey: 1 })
+    /// ));
+    ///
+    /// let mut troop = MonkeyTroop::from_monkeys(pair(1, 0)).unwrap();
+    /// assert_eq!(2, troop.modulus());
+    /// troop.run(3).unwrap();
+    /// assert_eq!(&[6, 6], troop.inspection_counts());
+    /// ```
+    pub fn from_monkeys(mut monkeys: Vec<Monkey<T>>) -> Result<Self, ParseError> {
+        if monkeys.len() < 2 {
+            return Err(ParseError::TooFewMonkeys {
+                found: monkeys.len(),
+            });
+        }
+
+        for (i, monkey) in monkeys.iter_mut().enumerate() {
+            monkey.index = i;
+        }
+
+        for (i, monkey) in monkeys.iter().enumerate() {
+            if monkey.test_mod.is_zero() {
+                return Err(ParseError::ZeroDivisor { monkey: i });
+            }
+            for target in [monkey.true_monkey_index, monkey.false_monkey_index] {
+                if target >= monkeys.len() {
+                    return Err(ParseError::InvalidTarget { monkey: i, target });
+                }
+                if target == i {
+                    return Err(ParseError::SelfThrow { monkey: i });
+                }
+            }
+        }
+
+        let modulo = Self::modulus_of(&monkeys)?;
+        for monkey in &mut monkeys {
+            monkey.prepare(modulo);
+        }
+
+        Ok(MonkeyTroop {
+            inspections: vec![0; monkeys.len()],
+            modulo,
+            modular: monkeys.iter().all(|m| m.operation.is_modular()),
+            worry_divisor: T::one(),
+            checked: false,
+            rounds_elapsed: 0,
+            next_monkey: 0,
+            turn_left: None,
+            #[cfg(feature = "log")]
+            round_start: None,
+            total_items: monkeys.iter().map(|m| m.items.len()).sum(),
+            start: ResetPoint {
+                items: monkeys
+                    .iter()
+                    .map(|m| m.items.iter().copied().collect())
+                    .collect(),
+                inspections: vec![0; monkeys.len()],
+                rounds: 0,
+                next_monkey: 0,
+            },
+            monkeys,
+        })
+    }
+
+    /// Divides worry levels by `worry_divisor` after every inspection, as in Part 1. Dividing a
+    /// worry level doesn't give the same result modulo the troop's modulus as dividing what's left
+    /// of it, so worry levels are then kept raw, and operations fail with
+    /// `ArithmeticError::Overflow` if they don't fit in the worry type
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    ///
+    /// troop.run(20).unwrap();
+    /// assert_eq!(10605, troop.monkey_business());
+    /// # }
+    /// ```
+    ///
+    /// Worry levels beyond the modulus are divided as they are
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{MonkeyTroop, ThrownItem};
+    ///
+    /// let input = "Monkey 0:
+    ///   Starting items: 3
+    ///   Operation: new = old * 3
+    ///   Test: divisible by 3
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 2
+    ///
+    /// Monkey 1:
+    ///   Starting items:
+    ///   Operation: new = old
+    ///   Test: divisible by 2
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0
+    ///
+    /// Monkey 2:
+    ///   Starting items:
+    ///   Operation: new = old
+    ///   Test: divisible by 1
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0";
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap().with_worry_divisor(3);
+    /// assert_eq!(6, troop.modulus());
+    ///
+    /// // 9 / 3 is divisible by 3, though (9 mod 6) / 3 isn't
+    /// let (_, thrown) = troop.throws(1).next().unwrap().unwrap();
+    /// assert_eq!(
+    ///     ThrownItem {
+    ///         was: 3,
+    ///         became: 9,
+    ///         item: 3,
+    ///         to_monkey: 1
+    ///     },
+    ///     thrown
+    /// );
+    /// troop.run_checked_modulo(20).unwrap();
+    /// # }
+    /// ```
+    pub fn with_worry_divisor(mut self, worry_divisor: T) -> Self {
+        self.worry_divisor = worry_divisor;
+        self
+    }
+
+    /// Stops keeping worry levels below the troop's modulus, so they're the raw values from the
+    /// puzzle description. Worry levels can then only grow as large as the worry type allows,
+    /// beyond which they wrap around, or with `with_checked_arithmetic` fail to
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let bounded = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let mut raw = bounded.clone().without_modulus();
+    /// let mut bounded = bounded;
+    ///
+    /// bounded.run(3).unwrap();
+    /// raw.run(3).unwrap();
+    ///
+    /// let largest = |troop: &MonkeyTroop| troop.held_items().into_iter().flatten().max();
+    /// assert!(largest(&bounded).unwrap() < 96577);
+    /// assert!(largest(&raw).unwrap() > 96577);
+    /// assert_eq!(bounded.inspection_counts(), raw.inspection_counts());
+    ///
+    /// // Without the modulus, squaring soon overflows
+    /// let mut checked = raw.with_checked_arithmetic();
+    /// assert!(checked.run(20).is_err());
+    /// # }
+    /// ```
+    pub fn without_modulus(mut self) -> Self {
+        self.modulo = T::max_value();
+        for monkey in &mut self.monkeys {
+            monkey.prepare(self.modulo);
+        }
+        self
+    }
+
+    /// Captures the current state of the troop, or returns `None` if a monkey's operation is a
+    /// closure given to `Monkey::with_operation`, which a snapshot can't capture
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use advent_of_code_2022_11::{MonkeyTroop, TroopSnapshot};
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// troop.run(5000).unwrap();
+    ///
+    /// let saved = serde_json::to_string(&troop.snapshot().unwrap()).unwrap();
+    /// let snapshot: TroopSnapshot = serde_json::from_str(&saved).unwrap();
+    /// let mut resumed = MonkeyTroop::from_snapshot(snapshot).unwrap();
+    ///
+    /// resumed.run(5000).unwrap();
+    /// assert_eq!(&[52166, 47830, 1938, 52013], resumed.inspection_counts());
+    /// # }
+    /// ```
+    ///
+    /// ```
+    /// use advent_of_code_2022_11::{Monkey, MonkeyTroop};
+    ///
+    /// let monkeys = vec![
+    ///     Monkey::with_operation([1], Box::new(|old| old * 2), 2, 1, 1),
+    ///     Monkey::builder().targets(0, 0).build(),
+    /// ];
+    /// let troop = MonkeyTroop::from_monkeys(monkeys).unwrap();
+    /// assert!(troop.snapshot().is_none());
+    /// ```
+    pub fn snapshot(&self) -> Option<TroopSnapshot<T>> {
+        if self.monkeys.iter().any(|m| m.custom_op.is_some()) {
+            return None;
+        }
+
+        Some(TroopSnapshot {
+            monkeys: self
+                .monkeys
+                .iter()
+                .zip(&self.inspections)
+                .map(|(monkey, &inspections)| MonkeySnapshot {
+                    items: monkey.items.iter().copied().collect(),
+                    inspections,
+                    operation: monkey.operation,
+                    test_mod: monkey.test_mod,
+                    true_monkey_index: monkey.true_monkey_index,
+                    false_monkey_index: monkey.false_monkey_index,
+                })
+                .collect(),
+            worry_divisor: self.worry_divisor,
+            checked: self.checked,
+            rounds: self.rounds_elapsed,
+            next_monkey: self.next_monkey,
+            unbounded: self.modulo == T::max_value(),
+        })
+    }
+
+    /// Restores a troop from a snapshot taken with `MonkeyTroop::snapshot`
+    /// # Errors
+    /// Returns a `ParseError` if the snapshot's monkeys don't make a valid troop, as for
+    /// `MonkeyTroop::from_monkeys`, or one takes its operation's result modulo zero
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use advent_of_code_2022_11::{MonkeyTroop, ParseError, TroopSnapshot};
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let saved = serde_json::to_string(&troop.snapshot().unwrap()).unwrap();
+    ///
+    /// let tampered = saved.replacen(r#""true_monkey_index":2"#, r#""true_monkey_index":7"#, 1);
+    /// let snapshot: TroopSnapshot = serde_json::from_str(&tampered).unwrap();
+    /// assert!(matches!(
+    ///     MonkeyTroop::from_snapshot(snapshot),
+    ///     Err(ParseError::InvalidTarget { monkey: 0, target: 7 })
+    /// ));
+    ///
+    /// let tampered = saved.replacen(r#""test_mod":23"#, r#""test_mod":0"#, 1);
+    /// let snapshot: TroopSnapshot = serde_json::from_str(&tampered).unwrap();
+    /// assert!(matches!(
+    ///     MonkeyTroop::from_snapshot(snapshot),
+    ///     Err(ParseError::ZeroDivisor { monkey: 0 })
+    /// ));
+    /// # }
+    /// ```
+    pub fn from_snapshot(snapshot: TroopSnapshot<T>) -> Result<Self, ParseError> {
+        let (monkeys, inspections): (Vec<_>, Vec<_>) = snapshot
+            .monkeys
+            .into_iter()
+            .enumerate()
+            .map(|(index, m)| {
+                (
+                    Monkey {
+                        index,
+                        items: m.items.into(),
+                        operation: m.operation,
+                        test_mod: m.test_mod,
+                        true_monkey_index: m.true_monkey_index,
+                        false_monkey_index: m.false_monkey_index,
+                        fast_op: None,
+                        custom_op: None,
+                    },
+                    m.inspections,
+                )
+            })
+            .unzip();
+        for (i, monkey) in monkeys.iter().enumerate() {
+            if let Some(modulo) = monkey.operation.modulo.filter(|m| m.is_zero()) {
+                return Err(ParseError::BadOperation {
+                    monkey: i,
+                    line: format!("Operation mod: {modulo}"),
+                    source: None,
+                });
+            }
+        }
+
+        let mut troop = Self::from_monkeys(monkeys)?;
+        troop.inspections = inspections;
+        troop.worry_divisor = snapshot.worry_divisor;
+        troop.checked = snapshot.checked;
+        troop.rounds_elapsed = snapshot.rounds;
+        troop.next_monkey = snapshot.next_monkey;
+        troop.start.inspections.clone_from(&troop.inspections);
+        troop.start.rounds = troop.rounds_elapsed;
+        troop.start.next_monkey = troop.next_monkey;
+        Ok(match snapshot.unbounded {
+            true => troop.without_modulus(),
+            false => troop,
+        })
+    }
+
+    /// Replaces the items each monkey is holding with `items`, in monkey order, and zeroes the
+    /// inspection counts so the troop can be run afresh without parsing it again
+    /// # Panics
+    /// Panics if `items` doesn't hold exactly one list per monkey
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// troop.run(10000).unwrap();
+    ///
+    /// troop.reset_items(vec![vec![], vec![54, 65, 75, 74], vec![79, 60, 97], vec![74]]);
+    /// assert_eq!(&[0, 0, 0, 0], troop.inspection_counts());
+    ///
+    /// let mut fresh = MonkeyTroop::parse(&input.replace("79, 98", "")).unwrap();
+    /// troop.run(20).unwrap();
+    /// fresh.run(20).unwrap();
+    /// assert_eq!(fresh.inspection_counts(), troop.inspection_counts());
+    /// # }
+    /// ```
+    pub fn reset_items(&mut self, items: Vec<Vec<T>>) {
+        assert_eq!(
+            self.monkeys.len(),
+            items.len(),
+            "There should be one list of items per monkey"
+        );
+
+        for (monkey, items) in self.monkeys.iter_mut().zip(items) {
+            monkey.items = items.into();
+        }
+        self.inspections.fill(0);
+        self.rounds_elapsed = 0;
+        self.next_monkey = 0;
+        self.turn_left = None;
+        #[cfg(feature = "log")]
+        {
+            self.round_start = None;
+        }
+        self.total_items = self.monkeys.iter().map(|m| m.items.len()).sum();
+    }
+
+    /// Gives every monkey back the items it started with and zeroes the inspection counts and
+    /// rounds elapsed, rewinding the troop to how it was parsed or built. A troop restored from a
+    /// snapshot is rewound to the snapshot instead, inspection counts, rounds and all
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let start = troop.to_string();
+    ///
+    /// troop.run(10000).unwrap();
+    /// let first = (troop.inspection_counts().to_vec(), troop.to_string());
+    ///
+    /// troop.reset();
+    /// assert_eq!(start, troop.to_string());
+    /// assert_eq!(0, troop.rounds_elapsed());
+    ///
+    /// troop.run(10000).unwrap();
+    /// assert_eq!(first, (troop.inspection_counts().to_vec(), troop.to_string()));
+    /// # }
+    /// ```
+    ///
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// troop.run(5).unwrap();
+    /// troop.run_bounded(10).unwrap();
+    /// let restored = MonkeyTroop::from_snapshot(troop.snapshot().unwrap()).unwrap();
+    ///
+    /// let mut rerun = restored.clone();
+    /// rerun.run(100).unwrap();
+    /// rerun.reset();
+    /// assert!(restored.state_eq(&rerun));
+    /// assert_eq!(restored.inspection_counts(), rerun.inspection_counts());
+    /// assert_eq!(5, rerun.rounds_elapsed());
+    ///
+    /// rerun.run(100).unwrap();
+    /// troop.run(100).unwrap();
+    /// assert_eq!(troop.inspection_counts(), rerun.inspection_counts());
+    /// # }
+    /// ```
+    pub fn reset(&mut self) {
+        self.reset_items(self.start.items.clone());
+        self.inspections.clone_from(&self.start.inspections);
+        self.rounds_elapsed = self.start.rounds;
+        self.next_monkey = self.start.next_monkey;
+    }
+
+    /// Makes operations fail with `ArithmeticError::Overflow` if an intermediate result doesn't
+    /// fit in the worry type, instead of reducing it without overflowing
+    pub fn with_checked_arithmetic(mut self) -> Self {
+        self.checked = true;
+        self
+    }
+
+    /// The least common multiple of every monkey's test divisor and operation modulus, below which
+    /// worry levels can be kept without changing any test or operation result
+    /// # Errors
+    /// Returns `ParseError::ModulusOverflow` if it doesn't fit in the worry type
+    fn modulus_of(monkeys: &[Monkey<T>]) -> Result<T, ParseError> {
+        monkeys
+            .iter()
+            .flat_map(|m| core::iter::once(m.test_mod).chain(m.operation.modulo))
+            .try_fold(T::one(), |a, b| (a / a.gcd(&b)).checked_mul(&b))
+            .ok_or(ParseError::ModulusOverflow)
+    }
+
+    /// The modulus worry levels are kept below in a round that divides them by the worry divisor,
+    /// and whether operations are checked for overflow
+    fn bounds(&self) -> (T, bool) {
+        match self.worry_divisor.is_one() && self.modular {
+            true => (self.modulo, self.checked),
+            false => self.unbounded(),
+        }
+    }
+
+    /// The modulus and overflow checking for a round that changes worry levels in a way that
+    /// doesn't carry over to their remainders, so they have to be kept raw. Operations are checked
+    /// for overflow unless the troop was already made to keep raw worry levels without checking
+    fn unbounded(&self) -> (T, bool) {
+        let unbounded = T::max_value();
+        (unbounded, self.checked || self.modulo != unbounded)
+    }
+
+    /// The number of the round being played, or about to be played, saturating at the largest
+    /// worry level
+    fn round(&self) -> T {
+        T::from(self.rounds_elapsed + 1).unwrap_or_else(T::max_value)
+    }
+
+    /// Runs a single round, in which each monkey in turn inspects and throws all of its items
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails. The item it failed on stays
+    /// with its monkey, so the troop can carry on from there
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = concat!(
+    ///     "Monkey 0:\n",
+    ///     "  Starting items: 18446744073709551615\n",
+    ///     "  Operation: new = old * old\n",
+    ///     "  Test: divisible by 7\n",
+    ///     "    If true: throw to monkey 1\n",
+    ///     "    If false: throw to monkey 1\n",
+    ///     "\n",
+    ///     "Monkey 1:\n",
+    ///     "  Starting items:\n",
+    ///     "  Operation: new = old + 0\n",
+    ///     "  Test: divisible by 11\n",
+    ///     "    If true: throw to monkey 0\n",
+    ///     "    If false: throw to monkey 0"
+    /// );
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    ///
+    /// // (2^64 - 1)^2 is reduced modulo 77 without overflowing
+    /// troop.step_round().unwrap();
+    /// assert_eq!("Monkey 0: 71\nMonkey 1: ", troop.to_string());
+    /// # }
+    /// ```
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{ArithmeticError, MonkeyTroop};
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let mut troop = troop.with_worry_divisor(3);
+    ///
+    /// let held = |troop: &MonkeyTroop| troop.held_items().iter().map(Vec::len).sum::<usize>();
+    ///
+    /// // Raw worry levels are soon too large to square
+    /// let error = (0..10000).find_map(|_| troop.step_round().err());
+    /// assert!(matches!(error, Some(ArithmeticError::Overflow)));
+    /// assert_eq!(10, held(&troop));
+    ///
+    /// // Without the worry divisor the modulus applies again, and the round carries on
+    /// let mut troop = troop.with_worry_divisor(1);
+    /// troop.run(100).unwrap();
+    /// assert_eq!(10, held(&troop));
+    /// # }
+    /// ```
+    ///
+    /// With the `log` feature, every round logs how many items each monkey inspected at the
+    /// `debug` level. Nothing is counted unless that level is enabled
+    /// ```
+    /// # #[cfg(feature = "log")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    /// use log::{LevelFilter, Log, Metadata, Record};
+    /// use std::sync::Mutex;
+    ///
+    /// struct Capture(Mutex<Vec<String>>);
+    ///
+    /// impl Log for Capture {
+    ///     fn enabled(&self, _: &Metadata) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn log(&self, record: &Record) {
+    ///         self.0.lock().unwrap().push(record.args().to_string());
+    ///     }
+    ///
+    ///     fn flush(&self) {}
+    /// }
+    ///
+    /// static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+    /// log::set_logger(&LOGGER).unwrap();
+    /// log::set_max_level(LevelFilter::Debug);
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// troop.run(3).unwrap();
+    ///
+    /// let messages = LOGGER.0.lock().unwrap();
+    /// assert_eq!(3, messages.len());
+    /// assert_eq!("Round 1: monkeys inspected [2, 4, 3, 6] items", messages[0]);
+    /// # }
+    /// ```
+    pub fn step_round(&mut self) -> Result<(), ArithmeticError> {
+        let worry_divisor = self.worry_divisor;
+        let (modulo, checked) = self.bounds();
+        match worry_divisor.is_one() {
+            true => self.play_round(modulo, checked, |worry| worry, |_, _| ()),
+            false => self.play_round(modulo, checked, |worry| worry / worry_divisor, |_, _| ()),
+        }
+    }
+
+    /// Runs a single round, applying `reduce` to each worry level after the monkey's operation and
+    /// before its test instead of dividing by the worry divisor. Worry levels are kept raw as with
+    /// a worry divisor, since `reduce` needn't carry over to their remainders either, unless
+    /// `step_round_with_modulus` is used instead. Since no monkey throws to itself, each monkey's
+    /// turn ends once it has thrown the items it held when the turn began, however large worry
+    /// levels get
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .without_modulus();
+    /// let held = troop.held_items();
+    ///
+    /// troop.step_round_with(|worry| worry).unwrap();
+    /// // Monkey 0 goes first, so it only inspects what it started with
+    /// assert_eq!(held[0].len() as u32, troop.inspection_counts()[0]);
+    /// # }
+    /// ```
+    pub fn step_round_with(&mut self, reduce: impl Fn(T) -> T) -> Result<(), ArithmeticError> {
+        let (modulo, checked) = self.unbounded();
+        self.play_round(modulo, checked, reduce, |_, _| ())
+    }
+
+    /// Runs a single round like `step_round_with`, but keeps worry levels below the troop's
+    /// modulus as `step_round` does. It's up to the caller that `reduce` leaves the same remainder
+    /// modulo the modulus whether it's given a worry level or its remainder, as the identity,
+    /// adding a constant and multiplying by a constant do. Dividing doesn't, so Part 1 still needs
+    /// `with_worry_divisor`, which keeps raw worry levels
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let mut plain = troop.clone();
+    ///
+    /// troop.step_round_with_modulus(|worry| worry).unwrap();
+    /// plain.step_round().unwrap();
+    /// assert_eq!(plain.held_items(), troop.held_items());
+    /// # }
+    /// ```
+    pub fn step_round_with_modulus(
+        &mut self,
+        reduce: impl Fn(T) -> T,
+    ) -> Result<(), ArithmeticError> {
+        let (modulo, checked) = self.bounds();
+        self.play_round(modulo, checked, reduce, |_, _| ())
+    }
+
+    /// Runs a single round as `step_round` does, returning the worry levels of the items caught by
+    /// monkey `target` in the order it caught them
+    /// # Panics
+    /// Panics if there's no monkey `target`
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    ///
+    /// assert_eq!(vec![20, 23, 27, 26], troop.step_round_collecting(0).unwrap());
+    /// // Monkey 1 catches these after its turn, so it still holds them at the end of round 2
+    /// assert_eq!(vec![43, 49, 58, 55, 362], troop.step_round_collecting(1).unwrap());
+    /// assert_eq!("Monkey 1: 43, 49, 58, 55, 362", troop.to_string().lines().nth(1).unwrap());
+    /// # }
+    /// ```
+    pub fn step_round_collecting(&mut self, target: usize) -> Result<Vec<T>, ArithmeticError> {
+        assert!(
+            target < self.monkeys.len(),
+            "There should be a monkey {target}"
+        );
+
+        let worry_divisor = self.worry_divisor;
+        let (modulo, checked) = self.bounds();
+        let mut caught = Vec::new();
+        self.play_round(
+            modulo,
+            checked,
+            |worry| worry / worry_divisor,
+            |to_monkey, item| {
+                if to_monkey == target {
+                    caught.push(item);
+                }
+            },
+        )?;
+        Ok(caught)
+    }
+
+    /// Runs a single round modulo `modulo`, or what's left of one, applying `reduce` after each
+    /// operation and telling `on_throw` which monkey each item was thrown to
+    fn play_round(
+        &mut self,
+        modulo: T,
+        checked: bool,
+        reduce: impl Fn(T) -> T,
+        mut on_throw: impl FnMut(usize, T),
+    ) -> Result<(), ArithmeticError> {
+        while let Some((_, thrown)) = self.inspect_step(modulo, checked, &reduce)? {
+            on_throw(thrown.to_monkey, thrown.item);
+        }
+
+        Ok(())
+    }
+
+    /// Whether any monkey's operation refers to the round, so that the rounds can't repeat
+    #[cfg(feature = "std")]
+    fn refers_to_round(&self) -> bool {
+        self.monkeys.iter().any(|m| m.operation.uses_round())
+    }
+
+    /// Whether no monkey has taken its turn yet in the round about to be played
+    fn at_round_start(&self) -> bool {
+        self.next_monkey == 0 && self.turn_left.is_none()
+    }
+
+    /// Has the monkey whose turn it is inspect and throw its next item modulo `modulo`, moving on
+    /// to the next monkey once one has nothing left to throw. Returns the index of the monkey that
+    /// threw along with the throw, or `None` once the round is over. Every way of running the troop
+    /// goes through here, so a round one of them stops part way through is finished by the next
+    fn inspect_step(
+        &mut self,
+        modulo: T,
+        checked: bool,
+        reduce: &impl Fn(T) -> T,
+    ) -> Result<Option<(usize, ThrownItem<T>)>, ArithmeticError> {
+        #[cfg(feature = "log")]
+        if self.at_round_start() && log::log_enabled!(log::Level::Debug) {
+            self.round_start = Some(self.inspections.clone());
+        }
+        let round = self.round();
+        while let Some(monkey) = self.monkeys.get_mut(self.next_monkey) {
+            let i = self.next_monkey;
+            let left = self.turn_left.get_or_insert(monkey.items.len());
+            if let Some(thrown) = monkey.inspect_next(modulo, round, checked, reduce)? {
+                *left -= 1;
+                self.inspections[i] += 1;
+                self.monkeys[thrown.to_monkey].catch(thrown.item);
+                return Ok(Some((i, thrown)));
+            }
+
+            debug_assert_eq!(
+                Some(0),
+                self.turn_left,
+                "Each monkey should throw exactly the items it held when its turn began"
+            );
+            self.turn_left = None;
+            self.next_monkey += 1;
+        }
+
+        self.next_monkey = 0;
+        self.rounds_elapsed += 1;
+        debug_assert_eq!(
+            self.total_items,
+            self.monkeys.iter().map(|m| m.items.len()).sum::<usize>(),
+            "Items should only ever be thrown between monkeys"
+        );
+        #[cfg(feature = "log")]
+        if let Some(before) = self.round_start.take() {
+            let inspected: Vec<_> = self
+                .inspections
+                .iter()
+                .zip(before)
+                .map(|(after, before)| after - before)
+                .collect();
+            log::debug!("Round {round}: monkeys inspected {inspected:?} items");
+        }
+
+        Ok(None)
+    }
+
+    /// Runs `n_rounds` rounds, taking time linear in `n_rounds` and in the number of items. Runs
+    /// carry on from wherever the troop left off
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let mut split = MonkeyTroop::parse(input).unwrap();
+    /// let mut whole = MonkeyTroop::parse(input).unwrap();
+    ///
+    /// split.run(20).unwrap();
+    /// split.run(80).unwrap();
+    /// whole.run(100).unwrap();
+    /// assert_eq!(whole.inspection_counts(), split.inspection_counts());
+    /// assert_eq!(whole.to_string(), split.to_string());
+    /// # }
+    /// ```
+    pub fn run(&mut self, n_rounds: u64) -> Result<(), ArithmeticError> {
+        for _ in 0..n_rounds {
+            self.step_round()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `n_rounds` rounds like `run`, while checking the modulo trick against a copy of the troop
+    /// that keeps the raw worry levels instead. Every item has to be thrown to the same monkey by
+    /// both, for as long as the raw worry levels fit in the worry type, after which the rounds are
+    /// run unchecked
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Panics
+    /// Panics if keeping worry levels below the modulus changes where an item is thrown
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    ///
+    /// let mut troop: MonkeyTroop<u128> = input.parse().unwrap();
+    /// troop.run_checked_modulo(10000).unwrap();
+    /// assert_eq!(2713310158, troop.monkey_business());
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap().with_worry_divisor(3);
+    /// troop.run_checked_modulo(20).unwrap();
+    /// assert_eq!(10605, troop.monkey_business());
+    /// # }
+    /// ```
+    pub fn run_checked_modulo(&mut self, n_rounds: u64) -> Result<(), ArithmeticError> {
+        let worry_divisor = self.worry_divisor;
+        let reduce = move |worry: T| match worry_divisor.is_one() {
+            true => worry,
+            false => worry / worry_divisor,
+        };
+        let (modulo, checked) = self.bounds();
+        let mut unbounded = Some(self.clone().without_modulus().with_checked_arithmetic());
+
+        for _ in 0..n_rounds {
+            let mut targets = Vec::new();
+            self.play_round(modulo, checked, reduce, |to_monkey, _| {
+                targets.push(to_monkey)
+            })?;
+
+            // Once a raw worry level overflows there's nothing left to check against
+            let mut raw_targets = Vec::new();
+            unbounded = unbounded.and_then(|mut raw| {
+                let (modulo, checked) = raw.bounds();
+                raw.play_round(modulo, checked, reduce, |to_monkey, _| {
+                    raw_targets.push(to_monkey)
+                })
+                .ok()
+                .map(|()| raw)
+            });
+            if unbounded.is_some() {
+                assert_eq!(
+                    raw_targets, targets,
+                    "The modulo trick changed where an item was thrown in round {}",
+                    self.rounds_elapsed
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs up to `max_rounds` rounds until the items every monkey holds are the same as after some
+    /// earlier round, and returns the number of rounds between the two, after which the monkeys
+    /// will keep repeating themselves. Returns `None` if no state repeats within `max_rounds`. The
+    /// rounds run are added to the inspection counts as with `run`. Only the items are compared, so
+    /// if any monkey's operation refers to the round, this returns `None` without running any
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = "Monkey 0:
+    ///   Starting items: 1
+    ///   Operation: new = old * 2
+    ///   Test: divisible by 5
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 1
+    ///
+    /// Monkey 1:
+    ///   Starting items:
+    ///   Operation: new = old + 0
+    ///   Test: divisible by 3
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0";
+    ///
+    /// // The item's worry level doubles modulo 15 every round: 1, 2, 4, 8, 1, ...
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// assert_eq!(Some(4), troop.detect_period(100).unwrap());
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// assert_eq!(None, troop.detect_period(3).unwrap());
+    /// assert_eq!(&[3, 3], troop.inspection_counts());
+    ///
+    /// let sample = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!(None, sample.clone().detect_period(1000).unwrap());
+    ///
+    /// // Halving undoes the doubling, so nothing changes from one round to the next
+    /// let mut troop = MonkeyTroop::parse(input).unwrap().with_worry_divisor(2);
+    /// assert_eq!(Some(1), troop.detect_period(100).unwrap());
+    ///
+    /// // The items repeat every round, but where they go doesn't
+    /// let mut troop = MonkeyTroop::parse(&input.replace("old * 2", "round * 1")).unwrap();
+    /// assert_eq!(None, troop.detect_period(100).unwrap());
+    /// assert_eq!(0, troop.rounds_elapsed());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn detect_period(&mut self, max_rounds: u64) -> Result<Option<u64>, ArithmeticError>
+    where
+        T: Hash,
+    {
+        if self.refers_to_round() {
+            return Ok(None);
+        }
+        // The items held part way through a round can't be compared with those between rounds
+        let mut seen = HashMap::new();
+        if self.at_round_start() {
+            seen.insert(self.state(), 0);
+        }
+        for round in 1..=max_rounds {
+            self.step_round()?;
+            if let Some(earlier) = seen.insert(self.state(), round) {
+                return Ok(Some(round - earlier));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Runs `n_rounds` rounds like `run`, but once the items every monkey holds repeat, skips over
+    /// whole repetitions by working out the inspections they'd make instead of simulating them.
+    /// Every distinct state reached is remembered until a repeat is found, so this trades memory
+    /// for time. If any monkey's operation refers to the round, this is just `run`
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails, or `ArithmeticError::Overflow`
+    /// if an inspection count gets too large to store
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt").replace("old * old", "old * 2");
+    /// let sample = MonkeyTroop::parse(&input).unwrap().with_worry_divisor(3);
+    /// let mut naive = sample.clone();
+    /// let mut fast = sample;
+    ///
+    /// naive.run(10000).unwrap();
+    /// fast.run_fast(10000).unwrap();
+    /// assert_eq!(naive.inspection_counts(), fast.inspection_counts());
+    /// assert_eq!(naive.to_string(), fast.to_string());
+    /// ```
+    ///
+    /// Once the state repeats quickly, even a billion rounds are cheap
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = "Monkey 0:
+    ///   Starting items: 1
+    ///   Operation: new = old * 2
+    ///   Test: divisible by 5
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 1
+    ///
+    /// Monkey 1:
+    ///   Starting items:
+    ///   Operation: new = old + 0
+    ///   Test: divisible by 3
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0";
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// troop.run_fast(1_000_000_001).unwrap();
+    ///
+    /// assert_eq!(&[1_000_000_001, 1_000_000_001], troop.inspection_counts());
+    /// assert_eq!("Monkey 0: 2\nMonkey 1: ", troop.to_string());
+    /// ```
+    ///
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = "Monkey 0:
+    ///   Starting items: 1
+    ///   Operation: new = round * 1
+    ///   Test: divisible by 2
+    ///     If true: throw to monkey 1
+    ///     If false: throw to monkey 2
+    ///
+    /// Monkey 1:
+    ///   Starting items:
+    ///   Operation: new = old + 0
+    ///   Test: divisible by 3
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0
+    ///
+    /// Monkey 2:
+    ///   Starting items:
+    ///   Operation: new = old + 0
+    ///   Test: divisible by 5
+    ///     If true: throw to monkey 0
+    ///     If false: throw to monkey 0";
+    ///
+    /// // The item is back where it started after every round, but goes to each monkey in turn
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// troop.run_fast(1000).unwrap();
+    /// assert_eq!(&[1000, 500, 500], troop.inspection_counts());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn run_fast(&mut self, n_rounds: u64) -> Result<(), ArithmeticError>
+    where
+        T: Hash,
+    {
+        if self.refers_to_round() {
+            return self.run(n_rounds);
+        }
+        let mut states = vec![self.state()];
+        let mut counts = vec![self.inspections.clone()];
+        let mut seen = HashMap::new();
+        if self.at_round_start() {
+            seen.insert(self.state(), 0);
+        }
+
+        for round in 1..=n_rounds {
+            self.step_round()?;
+            let state = self.state();
+            let Some(&start) = seen.get(&state) else {
+                seen.insert(state.clone(), round);
+                states.push(state);
+                counts.push(self.inspections.clone());
+                continue;
+            };
+
+            let period = round - start;
+            let remaining = n_rounds - round;
+            let (cycles, rest) = (remaining / period, remaining % period);
+            let (start, end) = (start as usize, (start + rest) as usize);
+            for (i, inspections) in self.inspections.iter_mut().enumerate() {
+                let per_cycle = u64::from(*inspections - counts[start][i]);
+                let extra = u64::from(counts[end][i] - counts[start][i]);
+                *inspections = cycles
+                    .checked_mul(per_cycle)
+                    .and_then(|skipped| skipped.checked_add(u64::from(*inspections) + extra))
+                    .and_then(|total| u32::try_from(total).ok())
+                    .ok_or(ArithmeticError::Overflow)?;
+            }
+            for (monkey, items) in self.monkeys.iter_mut().zip(&states[end].1) {
+                monkey.items = items.iter().copied().collect();
+            }
+            self.rounds_elapsed += remaining;
+            break;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `n_rounds` rounds, writing the items each monkey holds after every round to `out` in the
+    /// format of the puzzle description
+    /// # Errors
+    /// Returns a `RunError` if a monkey's operation fails or `out` can't be written to
+    /// # Examples
+    /// ```
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    /// let mut trace = Vec::new();
+    /// troop.run_traced(1, &mut trace).unwrap();
+    ///
+    /// assert_eq!(
+    ///     concat!(
+    ///         "After round 1, the monkeys are holding items with these worry levels:\n",
+    ///         "Monkey 0: 20, 23, 27, 26\n",
+    ///         "Monkey 1: 2080, 25, 167, 207, 401, 1046\n",
+    ///         "Monkey 2: \n",
+    ///         "Monkey 3: \n",
+    ///         "\n",
+    ///     ),
+    ///     String::from_utf8(trace).unwrap()
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn run_traced(&mut self, n_rounds: u64, out: &mut impl io::Write) -> Result<(), RunError> {
+        for round in 1..=n_rounds {
+            self.step_round()?;
+            writeln!(
+                out,
+                "After round {round}, the monkeys are holding items with these worry levels:"
+            )?;
+            writeln!(out, "{self}\n")?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `n_rounds` rounds, recording each monkey's inspection count at the end of every round
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let history = troop.run_recording(20).unwrap();
+    ///
+    /// assert_eq!(20, history.len());
+    /// assert_eq!(vec![2, 4, 3, 6], history[0]);
+    /// assert_eq!(vec![99, 97, 8, 103], history[19]);
+    /// # }
+    /// ```
+    pub fn run_recording(&mut self, n_rounds: u64) -> Result<Vec<Vec<u32>>, ArithmeticError> {
+        (0..n_rounds)
+            .map(|_| {
+                self.step_round()?;
+                Ok(self.inspections.clone())
+            })
+            .collect()
+    }
+
+    /// Runs `n_rounds` rounds, applying `reduce` as in `MonkeyTroop::step_round_with`
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    pub fn run_with(
+        &mut self,
+        n_rounds: u64,
+        reduce: impl Fn(T) -> T,
+    ) -> Result<(), ArithmeticError> {
+        for _ in 0..n_rounds {
+            self.step_round_with(&reduce)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `n_rounds` rounds, applying `reduce` as in `MonkeyTroop::step_round_with_modulus`
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    pub fn run_with_modulus(
+        &mut self,
+        n_rounds: u64,
+        reduce: impl Fn(T) -> T,
+    ) -> Result<(), ArithmeticError> {
+        for _ in 0..n_rounds {
+            self.step_round_with_modulus(&reduce)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs rounds until `max_inspections` items have been inspected, stopping part way through a
+    /// round if need be. A round left part way through is finished from where it stopped by
+    /// whichever run comes next, and counts as one of that run's rounds
+    /// # Errors
+    /// Returns an `ArithmeticError` if a monkey's operation fails
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{BudgetOutcome, Monkey, MonkeyTroop};
+    ///
+    /// let input = include_str!("../example-input.txt");
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// let outcome = troop.run_bounded(10).unwrap();
+    /// assert_eq!(BudgetOutcome::MidRound { rounds: 0, monkey: 3 }, outcome);
+    /// assert_eq!(&[2, 4, 3, 1], troop.inspection_counts());
+    /// assert_eq!(10, troop.iter().map(|monkey| monkey.items.len()).sum::<usize>());
+    ///
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// assert_eq!(BudgetOutcome::RoundBoundary { rounds: 1 }, troop.run_bounded(15).unwrap());
+    /// assert_eq!(&[2, 4, 3, 6], troop.inspection_counts());
+    ///
+    /// // Carrying on finishes the round rather than starting another
+    /// let mut troop = MonkeyTroop::parse(input).unwrap();
+    /// troop.run_bounded(10).unwrap();
+    /// assert_eq!(BudgetOutcome::MidRound { rounds: 0, monkey: 3 }, troop.run_bounded(4).unwrap());
+    /// assert_eq!(BudgetOutcome::RoundBoundary { rounds: 1 }, troop.run_bounded(1).unwrap());
+    /// assert_eq!(&[2, 4, 3, 6], troop.inspection_counts());
+    /// troop.run_bounded(3).unwrap();
+    /// troop.run(19).unwrap();
+    /// assert_eq!(&[99, 97, 8, 103], troop.inspection_counts());
+    /// assert_eq!(20, troop.rounds_elapsed());
+    ///
+    /// let empty = vec![
+    ///     Monkey::builder().targets(1, 1).build(),
+    ///     Monkey::builder().build(),
+    /// ];
+    /// let mut troop = MonkeyTroop::from_monkeys(empty).unwrap();
+    /// assert_eq!(BudgetOutcome::Idle { rounds: 0 }, troop.run_bounded(10).unwrap());
+    /// # }
+    /// ```
+    pub fn run_bounded(&mut self, max_inspections: u64) -> Result<BudgetOutcome, ArithmeticError> {
+        let worry_divisor = self.worry_divisor;
+        let reduce = |worry| worry / worry_divisor;
+        let (modulo, checked) = self.bounds();
+        let mut budget = max_inspections;
+        let mut rounds = 0;
+        let mut whole_round = self.at_round_start();
+        let mut inspected = false;
+
+        while budget > 0 {
+            match self.inspect_step(modulo, checked, &reduce)? {
+                Some(_) => {
+                    budget -= 1;
+                    inspected = true;
+                }
+                None if whole_round && !inspected => return Ok(BudgetOutcome::Idle { rounds }),
+                None => {
+                    rounds += 1;
+                    whole_round = true;
+                    inspected = false;
+                }
+            }
+        }
+
+        if !self.at_round_start() {
+            if let Some(monkey) =
+                (self.next_monkey..self.monkeys.len()).find(|&j| !self.monkeys[j].items.is_empty())
+            {
+                return Ok(BudgetOutcome::MidRound { rounds, monkey });
+            }
+            // None of the monkeys left to go holds anything, so the round is over
+            self.inspect_step(modulo, checked, &reduce)?;
+            rounds += 1;
+        }
+
+        Ok(BudgetOutcome::RoundBoundary { rounds })
+    }
+
+    /// Runs `n_rounds` rounds one inspection at a time, yielding the index of the monkey that
+    /// threw each item along with the throw
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{MonkeyTroop, ThrownItem};
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let throws: Vec<_> = troop.throws(1).collect::<Result<_, _>>().unwrap();
+    ///
+    /// assert_eq!(15, throws.len());
+    /// let first = ThrownItem {
+    ///     was: 79,
+    ///     became: 1501,
+    ///     item: 1501,
+    ///     to_monkey: 3,
+    /// };
+    /// assert_eq!((0, first), throws[0]);
+    /// # }
+    /// ```
+    ///
+    /// An operation doesn't have to refer to the old worry level at all
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt").replace("old * 19", "4 * 5");
+    /// let mut troop = MonkeyTroop::parse(&input).unwrap();
+    /// let throws: Vec<_> = troop.throws(100).collect::<Result<_, _>>().unwrap();
+    /// let from_monkey_0: Vec<_> = throws.iter().filter(|(from, _)| *from == 0).collect();
+    ///
+    /// assert!(!from_monkey_0.is_empty());
+    /// assert!(from_monkey_0.iter().all(|(_, thrown)| thrown.item == 20));
+    /// # }
+    /// ```
+    ///
+    /// Stopping part way through leaves the rest of the round to whichever run comes next
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!(4, troop.throws(1).take(4).count());
+    ///
+    /// troop.step_round().unwrap();
+    /// assert_eq!(&[2, 4, 3, 6], troop.inspection_counts());
+    /// assert_eq!(1, troop.rounds_elapsed());
+    /// # }
+    /// ```
+    pub fn throws(&mut self, n_rounds: u64) -> Throws<'_, T> {
+        Throws {
+            troop: self,
+            rounds_left: n_rounds,
+        }
+    }
+
+    /// Iterates over views of the monkeys in index order
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let adjacency: Vec<_> = troop
+    ///     .iter()
+    ///     .map(|monkey| [monkey.true_target, monkey.false_target])
+    ///     .collect();
+    ///
+    /// assert_eq!(vec![[2, 3], [2, 0], [1, 3], [0, 1]], adjacency);
+    /// # }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = MonkeyView<'_, T>> {
+        self.monkeys
+            .iter()
+            .zip(&self.inspections)
+            .map(|(monkey, &inspections)| MonkeyView {
+                items: &monkey.items,
+                test_mod: monkey.test_mod,
+                true_target: monkey.true_monkey_index,
+                false_target: monkey.false_monkey_index,
+                inspections,
+            })
+    }
+
+    /// Collects the state of every monkey so far into a `RunResult`
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::{MonkeyResult, MonkeyTroop};
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    /// troop.run(20).unwrap();
+    /// let result = troop.result();
+    ///
+    /// assert_eq!(4, result.monkeys.len());
+    /// assert_eq!(
+    ///     MonkeyResult {
+    ///         index: 0,
+    ///         inspections: 101,
+    ///         items: vec![10, 12, 14, 26, 34],
+    ///         divisor: 23,
+    ///         true_target: 2,
+    ///         false_target: 3,
+    ///     },
+    ///     result.monkeys[0]
+    /// );
+    /// assert_eq!(vec![245, 93, 53, 199, 115], result.monkeys[1].items);
+    /// assert_eq!(
+    ///     vec![101, 95, 7, 105],
+    ///     result.monkeys.iter().map(|m| m.inspections).collect::<Vec<_>>()
+    /// );
+    /// # }
+    /// ```
+    pub fn result(&self) -> RunResult<T> {
+        RunResult {
+            monkeys: self
+                .iter()
+                .enumerate()
+                .map(|(index, monkey)| MonkeyResult {
+                    index,
+                    inspections: monkey.inspections,
+                    items: monkey.items.iter().copied().collect(),
+                    divisor: monkey.test_mod,
+                    true_target: monkey.true_target,
+                    false_target: monkey.false_target,
+                })
+                .collect(),
+        }
+    }
+
+    /// The directed edges `(from, to)` of who throws to whom, listing each monkey's if true target
+    /// before its if false target
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![(0, 2), (0, 3), (1, 2), (1, 0), (2, 1), (2, 3), (3, 0), (3, 1)],
+    ///     troop.throw_graph()
+    /// );
+    /// # }
+    /// ```
+    pub fn throw_graph(&self) -> Vec<(usize, usize)> {
+        self.iter()
+            .enumerate()
+            .flat_map(|(from, monkey)| [(from, monkey.true_target), (from, monkey.false_target)])
+            .collect()
+    }
+
+    /// The monkeys that no monkey throws to, which only ever inspect their starting items
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let troop = MonkeyTroop::parse(input).unwrap();
+    /// assert!(troop.unreachable_monkeys().is_empty());
+    ///
+    /// let troop = MonkeyTroop::parse(&input.replace("monkey 3", "monkey 1")).unwrap();
+    /// assert_eq!(vec![3], troop.unreachable_monkeys());
+    /// # }
+    /// ```
+    pub fn unreachable_monkeys(&self) -> Vec<usize> {
+        let mut reachable = vec![false; self.monkeys.len()];
+        for (_, to) in self.throw_graph() {
+            reachable[to] = true;
+        }
+
+        (0..self.monkeys.len()).filter(|&i| !reachable[i]).collect()
+    }
+
+    /// The number of rounds the troop has been through since it was created or its items were last
+    /// reset
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!(0, troop.rounds_elapsed());
+    ///
+    /// troop.run(20).unwrap();
+    /// troop.step_round().unwrap();
+    /// troop.run(79).unwrap();
+    /// assert_eq!(100, troop.rounds_elapsed());
+    /// # }
+    /// ```
+    pub fn rounds_elapsed(&self) -> u64 {
+        self.rounds_elapsed
+    }
+
+    /// The modulus that worry levels are kept below, which is the least common multiple of every
+    /// monkey's test divisor and operation modulus
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    ///
+    /// assert_eq!(23 * 19 * 13 * 17, troop.modulus());
+    /// # }
+    /// ```
+    pub fn modulus(&self) -> T {
+        self.modulo
+    }
+
+    /// The number of bits needed to hold the modulus times the largest operand in any monkey's
+    /// operation, counting `old` and `round` as the modulus. A worry type at least this wide never
+    /// has to work around overflow in an operation
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let sample = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!(34, sample.required_bits());
+    ///
+    /// // Two primes just below 2^32, so the modulus just fits in a u64 but its square doesn't
+    /// let input = include_str!("../example-input.txt")
+    ///     .replace("divisible by 23", "divisible by 4294967291")
+    ///     .replace("divisible by 19", "divisible by 4294967279")
+    ///     .replace("divisible by 13", "divisible by 1")
+    ///     .replace("divisible by 17", "divisible by 1");
+    /// let large = MonkeyTroop::parse(&input).unwrap();
+    /// assert_eq!(4294967291 * 4294967279, large.modulus());
+    /// assert_eq!(128, large.required_bits());
+    /// # }
+    /// ```
+    pub fn required_bits(&self) -> u32 {
+        let max_operand = self
+            .monkeys
+            .iter()
+            .flat_map(|m| [m.operation.lhs, m.operation.rhs])
+            .map(|operand| match operand {
+                Expr::Num(c) => c,
+                Expr::Old | Expr::RoundVar => self.modulo,
+            })
+            .fold(self.modulo, T::max);
+        let to_u128 = |x: T| x.to_u128().expect("Worry levels should fit in a u128");
+        let bits = |x: u128| u128::BITS - x.leading_zeros();
+
+        let (modulo, operand) = (to_u128(self.modulo), to_u128(max_operand));
+        modulo
+            .checked_mul(operand)
+            .map_or(bits(modulo) + bits(operand), bits)
+    }
+
+    /// The worry levels of the items each monkey is holding right now, in monkey order
+    /// # Examples
+    /// Right after parsing, before any rounds, the troop is in its starting configuration
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    ///
+    /// assert_eq!(&[0, 0, 0, 0], troop.inspection_counts());
+    /// assert_eq!(
+    ///     vec![vec![79, 98], vec![54, 65, 75, 74], vec![79, 60, 97], vec![74]],
+    ///     troop.held_items()
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// After a round of Part 1
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    /// troop.step_round().unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         vec![20, 23, 27, 26],
+    ///         vec![2080, 25, 167, 207, 401, 1046],
+    ///         vec![],
+    ///         vec![],
+    ///     ],
+    ///     troop.held_items()
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// Items always add up to the same total as they're thrown around
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let total: usize = troop.held_items().iter().map(Vec::len).sum();
+    ///
+    /// for _ in 0..5 {
+    ///     troop.step_round().unwrap();
+    ///     let held = troop.held_items();
+    ///     assert_eq!(total, held.iter().map(Vec::len).sum::<usize>());
+    /// }
+    /// assert_eq!(10, total);
+    /// # }
+    /// ```
+    pub fn held_items(&self) -> Vec<Vec<T>> {
+        self.monkeys
+            .iter()
+            .map(|m| m.items.iter().copied().collect())
+            .collect()
+    }
+
+    /// The number of monkeys in the troop
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!(4, troop.len());
+    /// assert!(!troop.is_empty());
+    /// # }
+    /// ```
+    pub fn len(&self) -> usize {
+        self.monkeys.len()
+    }
+
+    /// Whether the troop has no monkeys, which is never the case since it can't be made with fewer
+    /// than two
+    pub fn is_empty(&self) -> bool {
+        self.monkeys.is_empty()
+    }
+
+    /// The number of items in play, which stays the same from round to round since items are only
+    /// ever thrown between monkeys
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!(10, troop.total_items());
+    ///
+    /// // Every round checks that no items were lost or duplicated when built with debug assertions
+    /// for _ in 0..1000 {
+    ///     troop.step_round().unwrap();
+    /// }
+    /// assert_eq!(10, troop.total_items());
+    /// # }
+    /// ```
+    pub fn total_items(&self) -> usize {
+        self.total_items
+    }
+
+    /// Whether every monkey in both troops holds the same items in the same order, regardless of
+    /// inspection counts or rounds elapsed, and are at the same point in a round. This is the state
+    /// `detect_period` and `run_fast` look for repeats of
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut one = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// let mut other = one.clone();
+    ///
+    /// one.run(5).unwrap();
+    /// other.run(5).unwrap();
+    /// other.set_inspections(0, 0);
+    /// assert!(one.state_eq(&other));
+    ///
+    /// other.step_round().unwrap();
+    /// assert!(!one.state_eq(&other));
+    /// # }
+    /// ```
+    pub fn state_eq(&self, other: &Self) -> bool {
+        self.state() == other.state()
+    }
+
+    /// The monkey whose turn it is and the items every monkey holds, which `state_eq` compares
+    fn state(&self) -> (usize, Vec<Vec<T>>) {
+        (self.next_monkey, self.held_items())
+    }
+
+    /// How many times each monkey has inspected an item so far, in monkey order
+    pub fn inspection_counts(&self) -> &[u32] {
+        &self.inspections
+    }
+
+    /// How many times monkey `monkey` has inspected an item so far, or `None` if there's no such
+    /// monkey
+    pub fn inspections(&self, monkey: usize) -> Option<u32> {
+        self.inspections.get(monkey).copied()
+    }
+
+    /// Monkey `monkey`'s operation, written as it is after `Operation:` in the puzzle input. A
+    /// monkey made by `Monkey::with_operation` has no such form, and gives `new = f(old)`
+    /// # Panics
+    /// Panics if there's no monkey `monkey`
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let input = include_str!("../example-input.txt");
+    /// let troop = MonkeyTroop::parse(input).unwrap();
+    ///
+    /// let lines: Vec<_> = input
+    ///     .lines()
+    ///     .filter_map(|line| line.trim().strip_prefix("Operation: "))
+    ///     .collect();
+    /// assert_eq!(4, lines.len());
+    /// for (monkey, line) in lines.into_iter().enumerate() {
+    ///     assert_eq!(line, troop.operation_str(monkey));
+    /// }
+    /// assert_eq!("new = old * old", troop.operation_str(2));
+    /// # }
+    /// ```
+    pub fn operation_str(&self, monkey: usize) -> String {
+        let monkey = self
+            .monkeys
+            .get(monkey)
+            .unwrap_or_else(|| panic!("There should be a monkey {monkey}"));
+        match monkey.custom_op {
+            Some(_) => "new = f(old)".to_string(),
+            None => monkey.operation.to_string(),
+        }
+    }
+
+    /// The monkey that monkey `monkey` would throw an item of worry level `item` to if it
+    /// inspected it now, applying its operation, the worry divisor and its test as in a round,
+    /// without changing the troop
+    /// # Errors
+    /// Returns an `ArithmeticError` if the monkey's operation fails
+    /// # Panics
+    /// Panics if there's no monkey `monkey`
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    ///
+    /// // 23 * 19 is divisible by 23, but 79 * 19 isn't
+    /// assert_eq!(2, troop.would_throw_to(0, 23).unwrap());
+    /// assert_eq!(3, troop.would_throw_to(0, 79).unwrap());
+    ///
+    /// // 23 * 19 / 3 rounds down to 145, which isn't
+    /// let troop = troop.with_worry_divisor(3);
+    /// assert_eq!(3, troop.would_throw_to(0, 23).unwrap());
+    /// assert_eq!(2, troop.would_throw_to(0, 69).unwrap());
+    /// # }
+    /// ```
+    pub fn would_throw_to(&self, monkey: usize, item: T) -> Result<usize, ArithmeticError> {
+        let monkey = self
+            .monkeys
+            .get(monkey)
+            .unwrap_or_else(|| panic!("There should be a monkey {monkey}"));
+        let worry_divisor = self.worry_divisor;
+        let (modulo, checked) = self.bounds();
+        let thrown = monkey.inspect(item, modulo, self.round(), checked, |worry| {
+            worry / worry_divisor
+        })?;
+        Ok(thrown.to_monkey)
+    }
+
+    /// Overwrites how many times monkey `monkey` has inspected an item, to set up a troop partway
+    /// through a run without simulating it
+    /// # Panics
+    /// Panics if there's no monkey `monkey`
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// for (monkey, count) in [101, 95, 7, 105].into_iter().enumerate() {
+    ///     troop.set_inspections(monkey, count);
+    /// }
+    ///
+    /// assert_eq!(Some(95), troop.inspections(1));
+    /// assert_eq!(None, troop.inspections(4));
+    /// assert_eq!(10605, troop.monkey_business());
+    /// # }
+    /// ```
+    pub fn set_inspections(&mut self, monkey: usize, count: u32) {
+        assert!(
+            monkey < self.inspections.len(),
+            "There should be a monkey {monkey}"
+        );
+        self.inspections[monkey] = count;
+    }
+
+    /// How many times each monkey has inspected an item so far, one line per monkey as at the end
+    /// of the puzzle description
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    /// troop.run(20).unwrap();
+    ///
+    /// assert_eq!(
+    ///     concat!(
+    ///         "Monkey 0 inspected items 101 times.\n",
+    ///         "Monkey 1 inspected items 95 times.\n",
+    ///         "Monkey 2 inspected items 7 times.\n",
+    ///         "Monkey 3 inspected items 105 times.",
+    ///     ),
+    ///     troop.summary()
+    /// );
+    /// # }
+    /// ```
+    pub fn summary(&self) -> String {
+        self.inspections
+            .iter()
+            .enumerate()
+            .map(|(i, n)| format!("Monkey {i} inspected items {n} times."))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The total number of inspections made by every monkey so far, which is also the number of
+    /// throws since every inspected item is thrown
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+    ///     .unwrap()
+    ///     .with_worry_divisor(3);
+    /// troop.run(20).unwrap();
+    ///
+    /// assert_eq!(101 + 95 + 7 + 105, troop.total_inspections());
+    /// # }
+    /// ```
+    pub fn total_inspections(&self) -> u64 {
+        self.inspections.iter().map(|&n| u64::from(n)).sum()
+    }
+
+    /// The index and inspection count of the busiest monkey so far, preferring the lowest index
+    /// in a tie
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// assert_eq!((0, 0), troop.busiest());
+    ///
+    /// troop.run(10000).unwrap();
+    /// assert_eq!((0, 52166), troop.busiest());
+    /// # }
+    /// ```
+    pub fn busiest(&self) -> (usize, u32) {
+        self.inspections
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(i, a), (j, b)| a.cmp(b).then(j.cmp(i)))
+            .expect("There should be at least one monkey")
+    }
+
+    /// Every monkey's index and inspection count so far, busiest first, with ties in index order
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use advent_of_code_2022_11::MonkeyTroop;
+    ///
+    /// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt")).unwrap();
+    /// troop.run(10000).unwrap();
+    ///
+    /// assert_eq!(
+    ///     vec![(0, 52166), (3, 52013), (1, 47830), (2, 1938)],
+    ///     troop.ranking()
+    /// );
+    ///
+    /// troop.reset();
+    /// assert_eq!(vec![(0, 0), (1, 0), (2, 0), (3, 0)], troop.ranking());
+    /// # }
+    /// ```
+    pub fn ranking(&self) -> Vec<(usize, u32)> {
+        let mut ranked: Vec<_> = self.inspections.iter().copied().enumerate().collect();
+        ranked.sort_by_key(|&(_, inspections)| Reverse(inspections));
+        ranked
+    }
+
+    /// The product of the inspection counts of the two busiest monkeys so far
+    pub fn monkey_business(&self) -> MonkeyBusiness {
+        let product = self
+            .monkey_business_top_n(2)
+            .expect("There should be at least two monkeys");
+        MonkeyBusiness(
+            usize::try_from(product).expect("The product of two counts should fit in a usize"),
+        )
+    }
+
+    /// The product of the inspection counts of the `top` busiest monkeys so far, or `None` if
+    /// there are fewer than `top` monkeys or the product doesn't fit in a `u128`
+    pub fn monkey_business_top_n(&self, top: usize) -> Option<u128> {
+        let mut inspections: Vec<_> = self.inspections.iter().map(|&n| u128::from(n)).collect();
+        inspections.sort();
+        inspections.reverse();
+        inspections
+            .get(..top)?
+            .iter()
+            .try_fold(1, |product: u128, &n| product.checked_mul(n))
+    }
+}
+
+/// An iterator over every throw made while running a `MonkeyTroop`, created by
+/// `MonkeyTroop::throws`
+pub struct Throws<'a, T = Item> {
+    troop: &'a mut MonkeyTroop<T>,
+    rounds_left: u64,
+}
+
+impl<T: Worry> Iterator for Throws<'_, T> {
+    type Item = Result<(usize, ThrownItem<T>), ArithmeticError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let worry_divisor = self.troop.worry_divisor;
+        let (modulo, checked) = self.troop.bounds();
+
+        while self.rounds_left > 0 {
+            match self
+                .troop
+                .inspect_step(modulo, checked, &|worry| worry / worry_divisor)
+            {
+                Ok(Some(throw)) => return Some(Ok(throw)),
+                Ok(None) => self.rounds_left -= 1,
+                Err(e) => {
+                    self.rounds_left = 0;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Parses a troop whose worry levels are stored in any `Worry` type
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::{MonkeyTroop, ParseError};
+///
+/// let input = include_str!("../example-input.txt");
+/// let mut wide: MonkeyTroop<u128> = input.parse().unwrap();
+/// let mut narrow: MonkeyTroop<u32> = input.parse().unwrap();
+///
+/// wide.run(10000).unwrap();
+/// narrow.run(10000).unwrap();
+/// assert_eq!(&[52166, 47830, 1938, 52013], wide.inspection_counts());
+/// assert_eq!(&[52166, 47830, 1938, 52013], narrow.inspection_counts());
+/// assert_eq!(2713310158, wide.monkey_business());
+///
+/// // Worry levels beyond `u64::MAX` only fit in the wider type
+/// let input = input.replace("Starting items: 74", "Starting items: 36893488147419103232");
+/// assert!(input.parse::<MonkeyTroop<u128>>().is_ok());
+/// assert!(matches!(
+///     MonkeyTroop::parse(&input),
+///     Err(ParseError::BadItems { monkey: 3, .. })
+/// ));
+/// ```
+#[cfg(feature = "std")]
+impl<T: Worry> FromStr for MonkeyTroop<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_troop(s)
+    }
+}
+
+/// Lists the items each monkey is holding, as in the puzzle description
+/// # Examples
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::MonkeyTroop;
+///
+/// let mut troop = MonkeyTroop::parse(include_str!("../example-input.txt"))
+///     .unwrap()
+///     .with_worry_divisor(3);
+/// troop.step_round().unwrap();
+///
+/// assert_eq!(
+///     concat!(
+///         "Monkey 0: 20, 23, 27, 26\n",
+///         "Monkey 1: 2080, 25, 167, 207, 401, 1046\n",
+///         "Monkey 2: \n",
+///         "Monkey 3: ",
+///     ),
+///     troop.to_string()
+/// );
+/// # }
+/// ```
+impl<T: Worry> fmt::Display for MonkeyTroop<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let items: Vec<_> = monkey.items.iter().map(T::to_string).collect();
+            write!(f, "Monkey {i}: {}", items.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Calculates the level of monkey business for Part 1, where worry is divided by 3 after each
+/// inspection, and returns it
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::monkey_business_part1;
+///
+/// assert_eq!(
+///     10605,
+///     monkey_business_part1(concat!(
+///         "Monkey 0:\n",
+///         "  Starting items: 79, 98\n",
+///         "  Operation: new = old * 19\n",
+///         "  Test: divisible by 23\n",
+///         "    If true: throw to monkey 2\n",
+///         "    If false: throw to monkey 3\n",
+///         "\n",
+///         "Monkey 1:\n",
+///         "  Starting items: 54, 65, 75, 74\n",
+///         "  Operation: new = old + 6\n",
+///         "  Test: divisible by 19\n",
+///         "    If true: throw to monkey 2\n",
+///         "    If false: throw to monkey 0\n",
+///         "\n",
+///         "Monkey 2:\n",
+///         "  Starting items: 79, 60, 97\n",
+///         "  Operation: new = old * old\n",
+///         "  Test: divisible by 13\n",
+///         "    If true: throw to monkey 1\n",
+///         "    If false: throw to monkey 3\n",
+///         "\n",
+///         "Monkey 3:\n",
+///         "  Starting items: 74\n",
+///         "  Operation: new = old + 3\n",
+///         "  Test: divisible by 17\n",
+///         "    If true: throw to monkey 0\n",
+///         "    If false: throw to monkey 1"
+/// ), 20).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_part1(input: &str, n_rounds: u64) -> Result<MonkeyBusiness, RunError> {
+    Ok(run_troop(input, n_rounds, 3)?.monkey_business())
+}
+
+/// Calculates the level of monkey business and returns it. The time taken is linear in
+/// `n_rounds` and in the number of items
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::monkey_business;
+///
+/// assert_eq!(
+///     2713310158,
+///     monkey_business(concat!(
+///         "Monkey 0:\n",
+///         "  Starting items: 79, 98\n",
+///         "  Operation: new = old * 19\n",
+///         "  Test: divisible by 23\n",
+///         "    If true: throw to monkey 2\n",
+///         "    If false: throw to monkey 3\n",
+///         "\n",
+///         "Monkey 1:\n",
+///         "  Starting items: 54, 65, 75, 74\n",
+///         "  Operation: new = old + 6\n",
+///         "  Test: divisible by 19\n",
+///         "    If true: throw to monkey 2\n",
+///         "    If false: throw to monkey 0\n",
+///         "\n",
+///         "Monkey 2:\n",
+///         "  Starting items: 79, 60, 97\n",
+///         "  Operation: new = old * old\n",
+///         "  Test: divisible by 13\n",
+///         "    If true: throw to monkey 1\n",
+///         "    If false: throw to monkey 3\n",
+///         "\n",
+///         "Monkey 3:\n",
+///         "  Starting items: 74\n",
+///         "  Operation: new = old + 3\n",
+///         "  Test: divisible by 17\n",
+///         "    If true: throw to monkey 0\n",
+///         "    If false: throw to monkey 1"
+/// ), 10000).unwrap());
+/// ```
+///
+/// No monkey has inspected anything before the first round
+/// ```
+/// use advent_of_code_2022_11::monkey_business;
+///
+/// assert_eq!(0, monkey_business(include_str!("../example-input.txt"), 0).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business(input: &str, n_rounds: u64) -> Result<MonkeyBusiness, RunError> {
+    Ok(run_troop(input, n_rounds, 1)?.monkey_business())
+}
+
+/// Calculates the level of monkey business like `monkey_business`, and also returns the indices
+/// of the two busiest monkeys that it's the product of, busiest first and preferring the lowest
+/// index in a tie
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::{monkey_business_detailed, MonkeyBusiness};
+///
+/// let input = include_str!("../example-input.txt");
+///
+/// assert_eq!(
+///     (MonkeyBusiness(2713310158), [0, 3]),
+///     monkey_business_detailed(input, 10000).unwrap()
+/// );
+/// assert_eq!(
+///     (MonkeyBusiness(0), [0, 1]),
+///     monkey_business_detailed(input, 0).unwrap()
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_detailed(
+    input: &str,
+    n_rounds: u64,
+) -> Result<(MonkeyBusiness, [usize; 2]), RunError> {
+    let troop = run_troop(input, n_rounds, 1)?;
+    let ranked = troop.ranking();
+
+    Ok((troop.monkey_business(), [ranked[0].0, ranked[1].0]))
+}
+
+/// Calculates the level of monkey business like `monkey_business`, but fails if an operation
+/// overflows an `Item` rather than reducing it without overflowing
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails or
+/// overflows
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::{monkey_business, monkey_business_checked};
+/// use advent_of_code_2022_11::{ArithmeticError, RunError};
+///
+/// let input = include_str!("../example-input.txt");
+/// assert_eq!(2713310158, monkey_business_checked(input, 10000).unwrap());
+///
+/// let input = concat!(
+///     "Monkey 0:\n",
+///     "  Starting items: 10000000000\n",
+///     "  Operation: new = old * old\n",
+///     "  Test: divisible by 4294967291\n",
+///     "    If true: throw to monkey 1\n",
+///     "    If false: throw to monkey 1\n",
+///     "\n",
+///     "Monkey 1:\n",
+///     "  Starting items:\n",
+///     "  Operation: new = old + 0\n",
+///     "  Test: divisible by 3\n",
+///     "    If true: throw to monkey 0\n",
+///     "    If false: throw to monkey 0"
+/// );
+/// assert!(monkey_business(input, 1).is_ok());
+/// assert!(matches!(
+///     monkey_business_checked(input, 1),
+///     Err(RunError::Arithmetic(ArithmeticError::Overflow))
+/// ));
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_checked(input: &str, n_rounds: u64) -> Result<MonkeyBusiness, RunError> {
+    let mut troop = MonkeyTroop::parse(input)?.with_checked_arithmetic();
+    troop.run(n_rounds)?;
+    Ok(troop.monkey_business())
+}
+
+/// Calculates the level of monkey business like `monkey_business`, but runs the simulation with
+/// `u128` worry levels instead of `Item`s when the input's modulus times its largest operand
+/// doesn't fit in an `Item`, or its numbers don't
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::{monkey_business, monkey_business_auto, MonkeyTroop};
+///
+/// let input = include_str!("../example-input.txt");
+/// assert_eq!(2713310158, monkey_business_auto(input, 10000).unwrap());
+///
+/// // A starting item that doesn't fit in an `Item`
+/// let huge = input.replace("Starting items: 74", "Starting items: 74, 100000000000000000000");
+/// assert!(monkey_business(&huge, 10000).is_err());
+/// assert_eq!(3282367488, monkey_business_auto(&huge, 10000).unwrap());
+///
+/// // Two primes just below 2^32, so the modulus fits in an `Item` but the square of a worry
+/// // level doesn't
+/// let input = input
+///     .replace("divisible by 23", "divisible by 4294967291")
+///     .replace("divisible by 19", "divisible by 4294967279")
+///     .replace("divisible by 13", "divisible by 1")
+///     .replace("divisible by 17", "divisible by 1");
+/// let wide: MonkeyTroop<u128> = input.parse().unwrap();
+/// assert!(wide.required_bits() > u64::BITS);
+/// assert_eq!(
+///     monkey_business(&input, 1000).unwrap().value() as u128,
+///     monkey_business_auto(&input, 1000).unwrap()
+/// );
+///
+/// // A starting item that doesn't fit in an `Item` as well
+/// let input = input.replace("Starting items: 74", "Starting items: 74, 100000000000000000000");
+/// assert!(monkey_business(&input, 20).is_err());
+/// assert_eq!(43680, monkey_business_auto(&input, 20).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_auto(input: &str, n_rounds: u64) -> Result<u128, RunError> {
+    let wide: MonkeyTroop<u128> = input.parse()?;
+    let narrow = wide.required_bits() <= Item::BITS
+        && wide
+            .held_items()
+            .into_iter()
+            .flatten()
+            .all(|item| Item::try_from(item).is_ok());
+    let ranked = match narrow {
+        true => run_troop(input, n_rounds, 1)?.ranking(),
+        false => {
+            let mut wide = wide;
+            wide.run(n_rounds)?;
+            wide.ranking()
+        }
+    };
+
+    Ok(u128::from(ranked[0].1) * u128::from(ranked[1].1))
+}
+
+/// Parses the puzzle input without running the simulation, checking that every monkey is
+/// well-formed and throws to a monkey that exists, and returns the number of monkeys
+/// # Errors
+/// Returns a `ParseError` if any monkey in the input is malformed or throws to itself or to a
+/// monkey that doesn't exist
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::{validate, ParseError};
+///
+/// let input = include_str!("../example-input.txt");
+/// assert_eq!(4, validate(input).unwrap());
+///
+/// assert!(matches!(
+///     validate(&input.replace("divisible by 13", "divisible by thirteen")),
+///     Err(ParseError::MissingTest { monkey: 2, .. })
+/// ));
+/// ```
+#[cfg(feature = "std")]
+pub fn validate(input: &str) -> Result<usize, ParseError> {
+    Ok(MonkeyTroop::parse(input)?.monkeys.len())
+}
+
+/// Reads the puzzle input from the file at `path`. With the `flate2` feature, gzip-compressed
+/// files are recognised by their header and decompressed
+/// # Errors
+/// Returns an `io::Error` if the file can't be read, isn't valid UTF-8 or can't be decompressed
+/// # Examples
+/// ```
+/// # #[cfg(feature = "flate2")]
+/// # {
+/// use advent_of_code_2022_11::{monkey_business_from_file, read_input};
+/// use flate2::{write::GzEncoder, Compression};
+/// use std::io::Write;
+///
+/// let input = include_str!("../example-input.txt");
+/// let path = std::env::temp_dir().join("read_input.txt.gz");
+/// let mut encoder = GzEncoder::new(std::fs::File::create(&path).unwrap(), Compression::default());
+/// encoder.write_all(input.as_bytes()).unwrap();
+/// encoder.finish().unwrap();
+///
+/// assert_eq!(input, read_input(&path).unwrap());
+/// assert_eq!(
+///     monkey_business_from_file("example-input.txt", 10000).unwrap(),
+///     monkey_business_from_file(&path, 10000).unwrap()
+/// );
+/// # std::fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn read_input(path: impl AsRef<Path>) -> io::Result<String> {
+    read_input_from(fs::File::open(path)?)
+}
+
+/// Reads the puzzle input from `reader`, such as standard input, decompressing it as `read_input`
+/// does
+/// # Errors
+/// Returns an `io::Error` if `reader` can't be read, or the input isn't valid UTF-8 or can't be
+/// decompressed
+/// # Examples
+/// ```
+/// # #[cfg(feature = "flate2")]
+/// # {
+/// use advent_of_code_2022_11::read_input_from;
+/// use flate2::{write::GzEncoder, Compression};
+/// use std::io::Write;
+///
+/// let input = include_str!("../example-input.txt");
+/// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+/// encoder.write_all(input.as_bytes()).unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// assert_eq!(input, read_input_from(compressed.as_slice()).unwrap());
+/// assert_eq!(input, read_input_from(input.as_bytes()).unwrap());
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn read_input_from(mut reader: impl io::Read) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    #[cfg(feature = "flate2")]
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return io::read_to_string(flate2::read::GzDecoder::new(bytes.as_slice()));
+    }
+
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Reads the puzzle input from the file at `path` and calculates the level of monkey business
+/// # Errors
+/// Returns a `RunError` if the file can't be read, any monkey in it is malformed or an operation
+/// fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::monkey_business_from_file;
+///
+/// let path = std::env::temp_dir().join("monkey_business_from_file.txt");
+/// std::fs::write(&path, include_str!("../example-input.txt")).unwrap();
+///
+/// assert_eq!(2713310158, monkey_business_from_file(&path, 10000).unwrap());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_from_file(
+    path: impl AsRef<Path>,
+    n_rounds: u64,
+) -> Result<MonkeyBusiness, RunError> {
+    monkey_business(&read_input(path)?, n_rounds)
+}
+
+/// Calculates the level of monkey business, applying `reduce` to each worry level after the
+/// monkey's operation and before its test. Worry levels are kept raw, as in
+/// `MonkeyTroop::step_round_with`, so `|x| x / 3` reproduces Part 1
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::monkey_business_with;
+///
+/// let input = include_str!("../example-input.txt");
+///
+/// assert_eq!(10605, monkey_business_with(input, 20, |x| x / 3).unwrap());
+///
+/// // Raw worry levels soon overflow without a worry divisor, unless the modulus is kept with
+/// // `monkey_business_with_modulus`
+/// assert!(monkey_business_with(input, 10000, |x| x).is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_with(
+    input: &str,
+    n_rounds: u64,
+    reduce: impl Fn(Item) -> Item,
+) -> Result<MonkeyBusiness, RunError> {
+    let mut troop = MonkeyTroop::parse(input)?;
+    troop.run_with(n_rounds, reduce)?;
+    Ok(troop.monkey_business())
+}
+
+/// Calculates the level of monkey business like `monkey_business_with`, but keeps worry levels
+/// below the troop's modulus, as in `MonkeyTroop::step_round_with_modulus`, so `reduce` must be
+/// compatible with it. Then `|x| x` reproduces Part 2
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// # #[cfg(feature = "std")]
+/// # {
+/// use advent_of_code_2022_11::monkey_business_with_modulus;
+///
+/// let input = include_str!("../example-input.txt");
+///
+/// assert_eq!(2713310158, monkey_business_with_modulus(input, 10000, |x| x).unwrap());
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_with_modulus(
+    input: &str,
+    n_rounds: u64,
+    reduce: impl Fn(Item) -> Item,
+) -> Result<MonkeyBusiness, RunError> {
+    let mut troop = MonkeyTroop::parse(input)?;
+    troop.run_with_modulus(n_rounds, reduce)?;
+    Ok(troop.monkey_business())
+}
+
+/// Calculates the product of the inspection counts of the `top` busiest monkeys, or `None` if
+/// there are fewer than `top` monkeys or the product doesn't fit in a `u128`
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::monkey_business_top_n;
+///
+/// let input = include_str!("../example-input.txt");
+///
+/// assert_eq!(Some(52166), monkey_business_top_n(input, 10000, 1).unwrap());
+/// assert_eq!(Some(129777624857140), monkey_business_top_n(input, 10000, 3).unwrap());
+/// assert_eq!(None, monkey_business_top_n(input, 10000, 5).unwrap());
+///
+/// // Products beyond `usize::MAX` are still exact
+/// let input = include_str!("../input.txt");
+/// assert_eq!(
+///     Some(146607111535214187968),
+///     monkey_business_top_n(input, 10000, 4).unwrap()
+/// );
+/// assert_eq!(None, monkey_business_top_n(input, 10000, 8).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn monkey_business_top_n(
+    input: &str,
+    n_rounds: u64,
+    top: usize,
+) -> Result<Option<u128>, RunError> {
+    Ok(run_troop(input, n_rounds, 1)?.monkey_business_top_n(top))
+}
+
+/// Runs the simulation and returns how many times each monkey inspected an item, in monkey order
+/// # Errors
+/// Returns a `RunError` if any monkey in the input is malformed or an operation fails
+/// # Examples
+/// ```
+/// use advent_of_code_2022_11::simulate;
+///
+/// let input = include_str!("../example-input.txt");
+///
+/// assert_eq!(vec![99, 97, 8, 103], simulate(input, 20).unwrap());
+/// assert_eq!(vec![52166, 47830, 1938, 52013], simulate(input, 10000).unwrap());
+/// ```
+///
+/// Squaring large worry levels doesn't overflow
+/// ```
+/// use advent_of_code_2022_11::simulate;
+///
+/// let input = concat!(
+///     "Monkey 0:\n",
+///     "  Starting items: 18446744073709551557, 4294967296\n",
+///     "  Operation: new = old * old\n",
+///     "  Test: divisible by 4294967291\n",
+///     "    If true: throw to monkey 1\n",
+///     "    If false: throw to monkey 2\n",
+///     "\n",
+///     "Monkey 1:\n",
+///     "  Starting items: 18446744073709551615\n",
+///     "  Operation: new = old * old\n",
+///     "  Test: divisible by 3\n",
+///     "    If true: throw to monkey 0\n",
+///     "    If false: throw to monkey 2\n",
+///     "\n",
+///     "Monkey 2:\n",
+///     "  Starting items: 64424509440\n",
+///     "  Operation: new = old + 7\n",
+///     "  Test: divisible by 5\n",
+///     "    If true: throw to monkey 0\n",
+///     "    If false: throw to monkey 1"
+/// );
+///
+/// assert_eq!(vec![3, 3996, 3999], simulate(input, 1000).unwrap());
+/// assert_eq!(
+///     vec![3, 3996, 3999],
+///     simulate(&input.replace("old * old", "old ^ 2"), 1000).unwrap()
+/// );
+/// ```
+///
+/// Worry levels are bounded by the least common multiple of the divisors, so shared divisors
+/// don't inflate it past what fits in a worry level
+/// ```
+/// use advent_of_code_2022_11::simulate;
+///
+/// let input = concat!(
+///     "Monkey 0:\n",
+///     "  Starting items: 18446744073709551557, 4294967296\n",
+///     "  Operation: new = old * old\n",
+///     "  Test: divisible by 4294967291\n",
+///     "    If true: throw to monkey 1\n",
+///     "    If false: throw to monkey 2\n",
+///     "\n",
+///     "Monkey 1:\n",
+///     "  Starting items: 18446744073709551615\n",
+///     "  Operation: new = old * 2\n",
+///     "  Test: divisible by 4294967291\n",
+///     "    If true: throw to monkey 0\n",
+///     "    If false: throw to monkey 2\n",
+///     "\n",
+///     "Monkey 2:\n",
+///     "  Starting items: 64424509440\n",
+///     "  Operation: new = old + 7\n",
+///     "  Test: divisible by 2\n",
+///     "    If true: throw to monkey 0\n",
+///     "    If false: throw to monkey 1"
+/// );
+///
+/// assert_eq!(vec![3, 3996, 4000], simulate(input, 1000).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn simulate(input: &str, n_rounds: u64) -> Result<Vec<u32>, RunError> {
+    Ok(run_troop(input, n_rounds, 1)?.inspection_counts().to_vec())
+}
+
+/// Parses arbitrary bytes as puzzle input and, if they're valid, runs a few rounds, ignoring any
+/// errors. This is an entry point for fuzzers, which should only ever see it panic on a bug
+/// # Examples
+/// ```
+/// # #[cfg(feature = "fuzzing")]
+/// # {
+/// use advent_of_code_2022_11::fuzz_run;
+///
+/// fuzz_run(include_bytes!("../example-input.txt"));
+/// fuzz_run(&[0xff, 0x00, 0x4d, 0x6f, 0x6e, 0x6b, 0x65, 0x79]);
+/// fuzz_run(b"Monkey 0:\n  Starting items: \xff, 7\n\n\n\n");
+/// fuzz_run(&[]);
+/// # }
+/// ```
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_run(data: &[u8]) {
+    if let Ok(mut troop) = MonkeyTroop::parse(&String::from_utf8_lossy(data)) {
+        let _ = troop.run(20);
+    }
+}
 
-    let mut inspections: Vec<_> = monkeys.iter().map(|m| m.num_inspections as usize).collect();
-    inspections.sort();
-    inspections.reverse();
-    inspections[0..=1].iter().product()
+#[cfg(feature = "std")]
+fn run_troop(input: &str, n_rounds: u64, worry_divisor: Item) -> Result<MonkeyTroop, RunError> {
+    let mut troop = MonkeyTroop::parse(input)?.with_worry_divisor(worry_divisor);
+    troop.run(n_rounds)?;
+    Ok(troop)
 }
@@ -1,12 +1,141 @@
+use advent_of_code_2022_11::{
+    monkey_business, monkey_business_part1, read_input, read_input_from, MonkeyTroop, RunError,
+};
 use std::env;
-use std::fs;
-use advent_of_code_2022_11::monkey_business;
+use std::io;
+use std::process;
+
+const USAGE: &str = "\
+Usage: advent-of-code-2022-11 [--json] [--part 1|2] [--rounds N] [FILE...] [N]
+
+Reads standard input when no FILE is given. When there is more than one argument and the last
+is a number, it is taken as the round count rather than a file: pass --rounds, or write a file
+named with digits as ./100, to avoid this. --rounds takes precedence over a trailing number,
+which takes precedence over $MONKEY_ROUNDS. Gzip-compressed input, from files or standard input,
+is decompressed when built with the flate2 feature.";
+
+/// Reports a mistake in the command line along with the usage, and exits
+fn usage_error(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!("{USAGE}");
+    process::exit(2);
+}
+
+/// Quotes `s` as a JSON string
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
 
 fn main() {
-    let args = env::args().collect::<Vec<_>>();
-    let file_path = &args[1];
-    let contents = fs::read_to_string(file_path).expect("Should have been able to read {file_path}");
+    let mut json = false;
+    let mut part = 2;
+    let mut rounds = None;
+    let mut args = Vec::new();
+    let mut all_args = env::args().skip(1);
+    while let Some(arg) = all_args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--part" => {
+                part = all_args
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .filter(|p| matches!(p, 1 | 2))
+                    .unwrap_or_else(|| usage_error("Part should be 1 or 2"))
+            }
+            "--rounds" => {
+                rounds = Some(
+                    all_args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or_else(|| usage_error("Rounds should be a number")),
+                )
+            }
+            _ if arg.starts_with("--") => usage_error(&format!("Unknown option {arg}")),
+            _ => args.push(arg),
+        }
+    }
+
+    let (worry_divisor, default_rounds) = match part {
+        1 => (3, 20),
+        _ => (1, 10000),
+    };
+    // With more than one argument, a trailing number is the round count rather than a file
+    let positional_rounds = match args.len() {
+        0 | 1 => None,
+        _ => args.last().and_then(|n| n.parse().ok()),
+    };
+    if positional_rounds.is_some() {
+        args.pop();
+    }
+    if args.is_empty() {
+        args.push("-".to_string());
+    }
+    let default_rounds = env::var("MONKEY_ROUNDS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(default_rounds);
+    let n_rounds = rounds.or(positional_rounds).unwrap_or(default_rounds);
+    let many = args.len() > 1;
+
+    let run = |file_path: &str| -> Result<(), RunError> {
+        let contents = match file_path {
+            "-" => read_input_from(io::stdin())?,
+            _ => read_input(file_path)?,
+        };
+        if json {
+            let mut troop = MonkeyTroop::parse(&contents)?.with_worry_divisor(worry_divisor);
+            troop.run(n_rounds)?;
+            let inspections = troop
+                .inspection_counts()
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>();
+            let file = match many {
+                true => format!(r#""file": {}, "#, json_string(file_path)),
+                false => String::new(),
+            };
+            println!(
+                r#"{{{file}"part": {part}, "rounds": {n_rounds}, "inspections": [{}], "monkey_business": {}}}"#,
+                inspections.join(", "),
+                troop.monkey_business().value()
+            );
+        } else {
+            let answer = match part {
+                1 => monkey_business_part1(&contents, n_rounds)?,
+                _ => monkey_business(&contents, n_rounds)?,
+            };
+            match many {
+                true => println!("{file_path}: Part {part}: {answer}"),
+                false => println!("Part {part}: {answer}"),
+            }
+        }
+        Ok(())
+    };
 
-    println!("The level of monkey business is {}", monkey_business(&contents, 20));
-    println!("The level of monkey business is {}", monkey_business(&contents, 10000));
+    let mut failed = 0;
+    for file_path in &args {
+        if let Err(e) = run(file_path) {
+            eprintln!("Could not run {file_path}: {e}");
+            failed += 1;
+        }
+    }
+    if many && !json {
+        println!("Ran {} files, {failed} failed", args.len());
+    }
+    if failed > 0 {
+        process::exit(1);
+    }
 }
@@ -0,0 +1,194 @@
+use std::process::{Command, Output};
+
+/// Builds a command for the binary in the crate's root directory with `args`
+fn command(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_advent-of-code-2022-11"));
+    command
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_remove("MONKEY_ROUNDS");
+    command
+}
+
+/// Runs the binary in the crate's root directory with `args`
+fn run(args: &[&str]) -> Output {
+    command(args).output().expect("The binary should run")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).expect("Output should be UTF-8")
+}
+
+#[test]
+fn rounds_flag_sets_the_round_count() {
+    let output = run(&["--rounds", "20", "example-input.txt"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        "Part 2: The level of monkey business is 10197\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn rounds_flag_takes_precedence_over_the_positional_count() {
+    let output = run(&["--rounds", "20", "example-input.txt", "10000"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        "Part 2: The level of monkey business is 10197\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn bad_rounds_or_part_is_a_usage_error() {
+    for args in [
+        &["--rounds", "abc", "example-input.txt"][..],
+        &["example-input.txt", "--rounds"],
+        &["--part", "3", "example-input.txt"],
+    ] {
+        let output = run(args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(Some(2), output.status.code(), "{args:?}");
+        assert!(stderr.contains("Usage:"), "{args:?}: {stderr}");
+        assert!(!stderr.contains("panicked"), "{args:?}: {stderr}");
+    }
+}
+
+#[test]
+fn unknown_options_are_a_usage_error() {
+    let output = run(&["--roudns", "20", "example-input.txt"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr.contains("Unknown option --roudns"), "{stderr}");
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn json_output_quotes_file_names() {
+    let dir = std::env::temp_dir().join("advent-of-code-2022-11-cli-json");
+    std::fs::create_dir_all(&dir).unwrap();
+    let awkward = dir.join("a \"quoted\"\tname\u{1}é.txt");
+    std::fs::copy("example-input.txt", &awkward).unwrap();
+    let awkward = awkward.to_str().unwrap();
+
+    let output = run(&["--json", "--part", "1", awkward, "example-input.txt"]);
+    assert!(output.status.success());
+
+    let results: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+    assert_eq!(2, results.len());
+    assert_eq!(awkward, results[0]["file"]);
+    assert_eq!("example-input.txt", results[1]["file"]);
+    assert_eq!(10605, results[0]["monkey_business"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn monkey_rounds_sets_the_default_round_count() {
+    let output = command(&["--json", "example-input.txt"])
+        .env("MONKEY_ROUNDS", "20")
+        .output()
+        .expect("The binary should run");
+
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(20, result["rounds"]);
+    assert_eq!(10197, result["monkey_business"]);
+}
+
+#[test]
+fn explicit_round_counts_take_precedence_over_monkey_rounds() {
+    for args in [
+        &["--rounds", "20", "example-input.txt"][..],
+        &["example-input.txt", "20"][..],
+    ] {
+        let output = command(args)
+            .env("MONKEY_ROUNDS", "1")
+            .output()
+            .expect("The binary should run");
+
+        assert!(output.status.success());
+        assert!(stdout(&output).ends_with(" 10197\n"), "{args:?}");
+    }
+}
+
+#[test]
+fn runs_every_file_and_counts_failures() {
+    let output = run(&["--part", "1", "example-input.txt", "input.txt"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        "example-input.txt: Part 1: The level of monkey business is 10605\n\
+         input.txt: Part 1: The level of monkey business is 56350\n\
+         Ran 2 files, 0 failed\n",
+        stdout(&output)
+    );
+
+    let output = run(&["--part", "1", "missing.txt", "example-input.txt"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(Some(1), output.status.code());
+    assert!(stderr.contains("Could not run missing.txt"), "{stderr}");
+    assert_eq!(
+        "example-input.txt: Part 1: The level of monkey business is 10605\n\
+         Ran 2 files, 1 failed\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn a_trailing_number_is_the_round_count_unless_written_as_a_path() {
+    let output = run(&["example-input.txt", "20"]);
+    assert_eq!(
+        "Part 2: The level of monkey business is 10197\n",
+        stdout(&output)
+    );
+
+    let output = run(&["example-input.txt", "./20"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(Some(1), output.status.code());
+    assert!(stderr.contains("Could not run ./20"), "{stderr}");
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn gzipped_input_is_decompressed_from_files_and_standard_input() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(include_bytes!("../example-input.txt"))
+        .unwrap();
+    let compressed = encoder.finish().unwrap();
+    let path = std::env::temp_dir().join("advent-of-code-2022-11-cli.txt.gz");
+    std::fs::write(&path, &compressed).unwrap();
+
+    let output = run(&[path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        "Part 2: The level of monkey business is 2713310158\n",
+        stdout(&output)
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    let mut child = command(&["--rounds", "20"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("The binary should run");
+    child.stdin.take().unwrap().write_all(&compressed).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        "Part 2: The level of monkey business is 10197\n",
+        stdout(&output)
+    );
+}
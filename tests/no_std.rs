@@ -0,0 +1,69 @@
+//! Builds and runs a troop from a `no_std` crate, which only has `core` and `alloc` to work with.
+//! Without the default features the library is `no_std` too, and builds for bare-metal targets
+//! such as with `cargo build --no-default-features --lib --target thumbv7em-none-eabihf`
+#![no_std]
+
+extern crate alloc;
+
+use advent_of_code_2022_11::{BudgetOutcome, Expr, Monkey, MonkeyTroop, Op};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The sample troop, built without parsing
+fn sample() -> MonkeyTroop {
+    let monkeys = vec![
+        Monkey::builder()
+            .items([79, 98])
+            .operation(Expr::Old, Op::Times, Expr::Num(19))
+            .test_divisor(23)
+            .targets(2, 3)
+            .build(),
+        Monkey::builder()
+            .items([54, 65, 75, 74])
+            .operation(Expr::Old, Op::Plus, Expr::Num(6))
+            .test_divisor(19)
+            .targets(2, 0)
+            .build(),
+        Monkey::builder()
+            .items([79, 60, 97])
+            .operation(Expr::Old, Op::Times, Expr::Old)
+            .test_divisor(13)
+            .targets(1, 3)
+            .build(),
+        Monkey::builder()
+            .items([74])
+            .operation(Expr::Old, Op::Plus, Expr::Num(3))
+            .test_divisor(17)
+            .targets(0, 1)
+            .build(),
+    ];
+    MonkeyTroop::from_monkeys(monkeys).unwrap()
+}
+
+#[test]
+fn runs_both_parts() {
+    let mut troop = sample().with_worry_divisor(3);
+    troop.run(20).unwrap();
+    assert_eq!(10605, troop.monkey_business());
+
+    let mut troop = sample();
+    troop.run(10000).unwrap();
+    assert_eq!(&[52166, 47830, 1938, 52013], troop.inspection_counts());
+    assert_eq!(2713310158, troop.monkey_business());
+}
+
+#[test]
+fn runs_one_inspection_at_a_time() {
+    let mut troop = sample();
+    let from: Vec<_> = troop.throws(1).map(|throw| throw.unwrap().0).collect();
+    assert_eq!(15, from.len());
+
+    let mut troop = sample();
+    assert_eq!(
+        BudgetOutcome::MidRound {
+            rounds: 0,
+            monkey: 3
+        },
+        troop.run_bounded(10).unwrap()
+    );
+}